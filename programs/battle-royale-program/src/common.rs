@@ -1,4 +1,7 @@
+use crate::constants::*;
+use crate::errors::*;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
 use mpl_token_metadata::state::Metadata;
 
 // Collection info, required to verify if an NFT belongs to a collection
@@ -19,62 +22,281 @@ pub enum CollectionInfo {
 impl CollectionInfo {
     // 1 + largest variant: 1 String of 8 chars, 1 Vev<Pubkey>, 1 hash of 32 bytes
     pub const LEN: usize = 1 + (4 + 32) + (4 + (32 * 5)) + 32;
+
+    // Root of the NFT whitelist, NFTs of a V1 collection must be whitelisted
+    pub fn whitelist_root(&self) -> Option<[u8; 32]> {
+        match self {
+            CollectionInfo::V1 { whitelist_root, .. } => Some(*whitelist_root),
+            CollectionInfo::V2 { .. } => None,
+        }
+    }
+}
+
+// Combat formulas of a battleground
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct CombatConfig {
+    pub base_health_points: u32,
+    pub health_points_per_defense: u32,
+    pub base_attack: u32,
+    pub base_defense: u32,
+    pub heal_divisor: u32,
+    // Budget players split between attack and defense
+    pub max_statistics: u32,
+    // Each point of damage is scaled by `mitigation / (mitigation + defense)`, 0 disables it
+    pub defense_mitigation: u32,
+    // Lowest damage dealt per action point once mitigated
+    pub min_damage: u32,
+    // Chances in basis points, rolled from the battleground seed
+    pub crit_chance: u16,
+    pub dodge_chance: u16,
+    // Damage multiplier of critical hits in basis points
+    pub crit_multiplier: u16,
+}
+
+impl Default for CombatConfig {
+    fn default() -> Self {
+        CombatConfig {
+            base_health_points: 750,
+            health_points_per_defense: 5,
+            base_attack: 100,
+            base_defense: 50,
+            heal_divisor: 2,
+            max_statistics: MAX_STATISTICS,
+            defense_mitigation: 0,
+            min_damage: 1,
+            crit_chance: 0,
+            dodge_chance: 0,
+            crit_multiplier: 20000,
+        }
+    }
+}
+
+impl CombatConfig {
+    pub const LEN: usize = 8 * 4 + 3 * 2;
+
+    // Every participant must be able to deal damage and heal, and the strongest one must not overflow
+    pub fn is_valid(&self) -> bool {
+        let max_defense = self.base_defense.checked_add(self.max_statistics);
+        let max_health_points = max_defense
+            .and_then(|defense| defense.checked_mul(self.health_points_per_defense))
+            .and_then(|health_points| health_points.checked_add(self.base_health_points));
+        let max_critical_damage = self
+            .base_attack
+            .checked_add(self.max_statistics)
+            .map(|attack| attack as u64 * self.crit_multiplier as u64 / 10000);
+        self.base_attack > 0
+            && self.min_damage > 0
+            && self.crit_chance <= 10000
+            && self.dodge_chance <= 10000
+            && self.crit_multiplier >= 10000
+            && max_critical_damage.is_some()
+            && max_critical_damage.unwrap() <= u32::MAX as u64
+            && self.heal_divisor > 0
+            && self.base_defense >= self.heal_divisor
+            && self.base_attack.checked_add(self.max_statistics).is_some()
+            && max_health_points.is_some()
+            && max_defense
+                .and_then(|defense| defense.checked_add(self.defense_mitigation))
+                .is_some()
+    }
+
+    pub fn max_health_points(&self, defense: u32) -> Result<u32> {
+        self.health_points_per_defense
+            .safe_mul(defense)?
+            .safe_add(self.base_health_points)
+    }
+
+    pub fn uses_randomness(&self) -> bool {
+        self.crit_chance > 0 || self.dodge_chance > 0
+    }
+
+    pub fn damage_per_point(&self, attack: u32, defense: u32, critical: bool) -> Result<u32> {
+        let attack = if critical {
+            to_u32(attack as u128 * self.crit_multiplier as u128 / 10000)?
+        } else {
+            attack
+        };
+        if self.defense_mitigation == 0 {
+            return Ok(attack);
+        }
+        let mitigated = attack as u128 * self.defense_mitigation as u128
+            / (self.defense_mitigation as u128 + defense as u128);
+        Ok(to_u32(mitigated)?.max(self.min_damage))
+    }
+
+    pub fn damage(
+        &self,
+        attack: u32,
+        defense: u32,
+        critical: bool,
+        action_points: u32,
+    ) -> Result<u32> {
+        self.damage_per_point(attack, defense, critical)?
+            .safe_mul(action_points)
+    }
+
+    pub fn heal(&self, defense: u32, action_points: u32) -> Result<u32> {
+        to_u32(action_points as u128 * defense as u128 / self.heal_divisor as u128)
+    }
+}
+
+// Paid resurrection of eliminated participants
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ReviveConfig {
+    // Paid in the pot currency, entirely added to the pot
+    pub fee: u64,
+    // Revives allowed to each participant in a round
+    pub max_revives: u32,
+    // Share of the max health points given back, in basis points
+    pub health_points: u16,
+    // Revives are closed once fewer participants remain
+    pub min_participants: u32,
+}
+
+impl ReviveConfig {
+    pub const LEN: usize = 8 + 4 + 2 + 4;
+
+    // Placements are given by the participants count at elimination, a revive shifts the following ones
+    // Closing revives before the paid ranks keeps every paid placement unique
+    pub fn is_valid(&self, payout_ranks: usize) -> bool {
+        self.max_revives > 0
+            && self.health_points > 0
+            && self.health_points <= 10000
+            && self.min_participants as usize >= payout_ranks
+    }
+}
+
+// Closing zone dealing damage to idle participants
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct StormConfig {
+    // Seconds after the start of the battle before the storm hits
+    pub grace_period: i64,
+    pub damage_per_day: u32,
+    // Seconds a participant is sheltered after spending action points
+    pub idle_period: i64,
+}
+
+impl StormConfig {
+    pub const LEN: usize = 8 + 4 + 8;
+
+    pub fn is_valid(&self) -> bool {
+        self.grace_period >= 0 && self.damage_per_day > 0 && self.idle_period >= 0
+    }
+}
+
+// Pacing of the participants actions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub struct ActionConfig {
+    // Most action points a participant can keep unspent
+    pub max_action_points_bank: Option<u32>,
+    // Minimum seconds between two actions of a participant
+    pub cooldown: Option<i64>,
+}
+
+impl ActionConfig {
+    pub const LEN: usize = (1 + 4) + (1 + 8);
+
+    pub fn is_valid(&self) -> bool {
+        self.max_action_points_bank.unwrap_or(1) > 0 && self.cooldown.unwrap_or(1) > 0
+    }
+}
+
+// Source of the participants attack and defense
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum StatsSource {
+    // Players split the statistics budget as they like
+    Declared,
+    // The creator publishes a merkle root over `mint -> (attack, defense)`
+    StatTable { root: [u8; 32] },
+    // Statistics are derived from a hash of the NFT mint
+    MintHash,
+}
+
+impl StatsSource {
+    // 1 + largest variant: 1 hash of 32 bytes
+    pub const LEN: usize = 1 + 32;
+}
+
+// How participants are split into teams
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum TeamMode {
+    // Every participant for themselves, all of them are in team 0
+    FreeForAll,
+    // Each team is made of the NFTs of one collection, the battleground collection is not used
+    ByCollection { collections: Vec<CollectionInfo> },
+    // Players pick one of `teams` when joining
+    PlayerChoice { teams: u8 },
+}
+
+impl TeamMode {
+    // 1 + largest variant: 1 Vec<CollectionInfo> of MAX_TEAMS collections
+    pub const LEN: usize = 1 + 4 + MAX_TEAMS * CollectionInfo::LEN;
+
+    pub fn team_count(&self) -> usize {
+        match self {
+            TeamMode::FreeForAll => 1,
+            TeamMode::ByCollection { collections } => collections.len(),
+            TeamMode::PlayerChoice { teams } => *teams as usize,
+        }
+    }
 }
 
 // Verify in the NFT belongs to the collection
-pub fn verify_collection(
-    metadata: &AccountInfo,
-    collection_info: &CollectionInfo,
-    whitelist_proof: Option<Vec<[u8; 32]>>,
-) -> bool {
-    let metadata = Metadata::from_account_info(metadata).unwrap();
+// Metadata that can't be parsed fails the verification instead of aborting the program
+pub fn verify_collection(metadata: &AccountInfo, collection_info: &CollectionInfo) -> Result<bool> {
+    if metadata.data_is_empty() {
+        return err!(BattleRoyaleError::CollectionVerificationFailed);
+    }
+    let metadata = Metadata::from_account_info(metadata)
+        .map_err(|_| error!(BattleRoyaleError::CollectionVerificationFailed))?;
 
     match collection_info {
         CollectionInfo::V1 {
             symbol,
             verified_creators,
-            whitelist_root,
+            ..
         } => {
             // Check if the symbol matches
             let trimmed_symbol = metadata.data.symbol.trim_matches(char::from(0));
             let valid_symbol = trimmed_symbol == symbol;
 
             // Check if at least one NFT creator exists in BucketState's verified creators
-            let creators = metadata.data.creators.unwrap();
-            let mut valid_creator = false;
-            if !verified_creators.is_empty() {
-                valid_creator = creators.iter().any(|creator| {
-                    creator.verified
-                        && verified_creators.iter().any(|additional_verified_creator| {
-                            creator.address == *additional_verified_creator
-                        })
-                });
-            }
+            let creators = metadata.data.creators.unwrap_or_default();
+            let valid_creator = creators.iter().any(|creator| {
+                creator.verified
+                    && verified_creators.iter().any(|additional_verified_creator| {
+                        creator.address == *additional_verified_creator
+                    })
+            });
 
-            // Check if NFT exists in whitelist
-            let leaf = anchor_lang::solana_program::keccak::hash(&metadata.mint.to_bytes()).0;
-            let in_whitelist = verify_proof(whitelist_proof.unwrap(), *whitelist_root, leaf);
-
-            valid_symbol && valid_creator && in_whitelist
+            Ok(valid_symbol && valid_creator)
         }
 
         CollectionInfo::V2 { collection_mint } => match metadata.collection {
             // Check that the collection field exists
-            None => false,
+            None => Ok(false),
             Some(collection) => {
                 // Check that the collection mint matches, and verified is true
-                collection.key == *collection_mint && collection.verified
+                Ok(collection.key == *collection_mint && collection.verified)
             }
         },
     }
 }
 
-pub fn verify_holder(proof: Vec<[u8; 32]>, root: [u8; 32], holder: [u8; 32]) -> bool {
-    verify_proof(
-        proof,
-        root,
-        anchor_lang::solana_program::keccak::hash(&holder).0,
-    )
+// Require a valid proof that the key is whitelisted, if there is a whitelist
+pub fn require_whitelisted(
+    root: Option<[u8; 32]>,
+    proof: Option<Vec<[u8; 32]>>,
+    key: &Pubkey,
+) -> Result<()> {
+    if let Some(root) = root {
+        let proof = proof.ok_or(BattleRoyaleError::WhitelistProofMissing)?;
+        require!(
+            verify_proof(proof, root, keccak::hash(&key.to_bytes()).0),
+            BattleRoyaleError::NotWhitelisted
+        );
+    }
+    Ok(())
 }
 
 pub fn verify_proof(proof: Vec<[u8; 32]>, root: [u8; 32], leaf: [u8; 32]) -> bool {
@@ -94,7 +316,275 @@ pub fn verify_proof(proof: Vec<[u8; 32]>, root: [u8; 32], leaf: [u8; 32]) -> boo
     computed_hash == root
 }
 
-pub fn action_points_available(start: i64, now: i64, action_points_per_day: u32) -> u32 {
-    let seconds_elapsed: u32 = (now - start) as u32;
-    action_points_per_day * seconds_elapsed / 86400
+// Leaf of the stat table for an NFT and its statistics
+pub fn stat_table_leaf(mint: &Pubkey, attack: u32, defense: u32) -> [u8; 32] {
+    keccak::hashv(&[
+        &mint.to_bytes(),
+        &attack.to_le_bytes(),
+        &defense.to_le_bytes(),
+    ])
+    .0
+}
+
+// Split of the statistics budget derived from the mint, the same NFT always gets the same split
+pub fn mint_hash_statistics(mint: &Pubkey, max_statistics: u32) -> (u32, u32) {
+    let hash = keccak::hash(&mint.to_bytes()).0;
+    let attack = u16::from_le_bytes([hash[0], hash[1]]) as u32 % (max_statistics + 1);
+    (attack, max_statistics - attack)
+}
+
+// Seed of a battle, mixing the revealed secret with a slot hash unknown when it was committed
+pub fn mix_seed(secret: &[u8; 32], slot_hash: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[secret, slot_hash]).0
+}
+
+// Roll of a participant action, anyone can replay it from the revealed seed
+pub fn action_roll(seed: &[u8; 32], participant: &Pubkey, nonce: u64) -> [u8; 32] {
+    keccak::hashv(&[seed, &participant.to_bytes(), &nonce.to_le_bytes()]).0
+}
+
+// Whether a chance in basis points succeeds, using the 8 bytes of the roll at `offset`
+pub fn roll_succeeds(roll: &[u8; 32], offset: usize, chance: u16) -> bool {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&roll[offset..offset + 8]);
+    u64::from_le_bytes(value) % 10000 < chance as u64
+}
+
+pub fn action_points_available(start: i64, now: i64, action_points_per_day: u32) -> Result<u64> {
+    let seconds_elapsed =
+        u64::try_from(now.safe_sub(start)?).map_err(|_| error!(BattleRoyaleError::MathOverflow))?;
+    to_u64(action_points_per_day as u128 * seconds_elapsed as u128 / 86400)
+}
+
+// Action points to spend for `amount`, at `per_point` for each point, rounded up
+pub fn action_points_needed(amount: u32, per_point: u32) -> Result<u32> {
+    let points = amount.safe_div(per_point)?;
+    if points.safe_mul(per_point)? < amount {
+        points.safe_add(1)
+    } else {
+        Ok(points)
+    }
+}
+
+// Share of an amount in basis points
+pub fn basis_points_of(amount: u64, basis_points: u16) -> Result<u64> {
+    to_u64(amount as u128 * basis_points as u128 / 10000)
+}
+
+pub fn to_u32(value: u128) -> Result<u32> {
+    u32::try_from(value).map_err(|_| error!(BattleRoyaleError::MathOverflow))
+}
+
+pub fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(BattleRoyaleError::MathOverflow))
+}
+
+// Checked arithmetic, overflows are reported as `MathOverflow` instead of panicking or wrapping
+pub trait SafeMath: Sized {
+    fn safe_add(self, other: Self) -> Result<Self>;
+    fn safe_sub(self, other: Self) -> Result<Self>;
+    fn safe_mul(self, other: Self) -> Result<Self>;
+    fn safe_div(self, other: Self) -> Result<Self>;
+}
+
+macro_rules! impl_safe_math {
+    ($($t:ty),*) => {
+        $(
+            impl SafeMath for $t {
+                fn safe_add(self, other: Self) -> Result<Self> {
+                    self.checked_add(other)
+                        .ok_or_else(|| error!(BattleRoyaleError::MathOverflow))
+                }
+
+                fn safe_sub(self, other: Self) -> Result<Self> {
+                    self.checked_sub(other)
+                        .ok_or_else(|| error!(BattleRoyaleError::MathOverflow))
+                }
+
+                fn safe_mul(self, other: Self) -> Result<Self> {
+                    self.checked_mul(other)
+                        .ok_or_else(|| error!(BattleRoyaleError::MathOverflow))
+                }
+
+                fn safe_div(self, other: Self) -> Result<Self> {
+                    self.checked_div(other)
+                        .ok_or_else(|| error!(BattleRoyaleError::MathOverflow))
+                }
+            }
+        )*
+    };
+}
+
+impl_safe_math!(u16, u32, u64, u128, i64);
+
+// Share of the prize pool awarded to `count` participants starting at `first_placement`
+// Basis points are relative to the payout table total, which excludes the dev and creator fees
+pub fn prize_for_placements(
+    prize_pool: u64,
+    payout_table: &[u16],
+    first_placement: u32,
+    count: u32,
+) -> u64 {
+    let total: u128 = payout_table.iter().map(|bps| *bps as u128).sum();
+    if total == 0 || first_placement == 0 {
+        return 0;
+    }
+    let basis_points: u128 = payout_table
+        .iter()
+        .skip(first_placement as usize - 1)
+        .take(count as usize)
+        .map(|bps| *bps as u128)
+        .sum();
+    (prize_pool as u128 * basis_points / total) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpl_token_metadata::state::{Collection, Creator, Data, Key, MAX_METADATA_LEN};
+
+    const SYMBOL: &str = "DAPE";
+
+    fn metadata(creators: Option<Vec<Creator>>, collection: Option<Collection>) -> Metadata {
+        Metadata {
+            key: Key::MetadataV1,
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            data: Data {
+                name: "Degen Ape".to_string(),
+                // Metadata strings are padded with null characters
+                symbol: format!("{:\0<10}", SYMBOL),
+                uri: String::new(),
+                seller_fee_basis_points: 0,
+                creators,
+            },
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection,
+            uses: None,
+        }
+    }
+
+    fn creator(address: Pubkey, verified: bool) -> Creator {
+        Creator {
+            address,
+            verified,
+            share: 100,
+        }
+    }
+
+    fn v1(verified_creator: Pubkey) -> CollectionInfo {
+        CollectionInfo::V1 {
+            symbol: SYMBOL.to_string(),
+            verified_creators: vec![verified_creator],
+            whitelist_root: [0; 32],
+        }
+    }
+
+    // Runs `verify_collection` against the metadata account holding `data`
+    fn verify(data: Vec<u8>, collection_info: &CollectionInfo) -> Result<bool> {
+        let key = Pubkey::new_unique();
+        let owner = mpl_token_metadata::id();
+        let mut lamports = 0;
+        let mut data = data;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        verify_collection(&account, collection_info)
+    }
+
+    fn account_data(metadata: &Metadata) -> Vec<u8> {
+        let mut data = metadata.try_to_vec().unwrap();
+        data.resize(MAX_METADATA_LEN, 0);
+        data
+    }
+
+    #[test]
+    fn verifies_v1_collection() {
+        let verified_creator = Pubkey::new_unique();
+        let metadata = metadata(Some(vec![creator(verified_creator, true)]), None);
+        assert!(verify(account_data(&metadata), &v1(verified_creator)).unwrap());
+    }
+
+    #[test]
+    fn rejects_no_creators() {
+        let metadata = metadata(None, None);
+        assert!(!verify(account_data(&metadata), &v1(Pubkey::new_unique())).unwrap());
+    }
+
+    #[test]
+    fn rejects_unverified_creator() {
+        let verified_creator = Pubkey::new_unique();
+        let metadata = metadata(Some(vec![creator(verified_creator, false)]), None);
+        assert!(!verify(account_data(&metadata), &v1(verified_creator)).unwrap());
+    }
+
+    #[test]
+    fn rejects_wrong_symbol() {
+        let verified_creator = Pubkey::new_unique();
+        let mut metadata = metadata(Some(vec![creator(verified_creator, true)]), None);
+        metadata.data.symbol = "SMB".to_string();
+        assert!(!verify(account_data(&metadata), &v1(verified_creator)).unwrap());
+    }
+
+    #[test]
+    fn verifies_v2_collection() {
+        let collection_mint = Pubkey::new_unique();
+        let metadata = metadata(
+            None,
+            Some(Collection {
+                verified: true,
+                key: collection_mint,
+            }),
+        );
+        let collection_info = CollectionInfo::V2 { collection_mint };
+        assert!(verify(account_data(&metadata), &collection_info).unwrap());
+    }
+
+    #[test]
+    fn rejects_unverified_collection() {
+        let collection_mint = Pubkey::new_unique();
+        let metadata = metadata(
+            None,
+            Some(Collection {
+                verified: false,
+                key: collection_mint,
+            }),
+        );
+        let collection_info = CollectionInfo::V2 { collection_mint };
+        assert!(!verify(account_data(&metadata), &collection_info).unwrap());
+    }
+
+    fn assert_verification_failed(result: Result<bool>) {
+        let expected = ProgramError::from(error!(BattleRoyaleError::CollectionVerificationFailed));
+        assert_eq!(ProgramError::from(result.unwrap_err()), expected);
+    }
+
+    #[test]
+    fn rejects_malformed_metadata() {
+        let collection_info = CollectionInfo::V2 {
+            collection_mint: Pubkey::new_unique(),
+        };
+
+        // Empty account
+        assert_verification_failed(verify(vec![], &collection_info));
+
+        // Wrong size
+        let metadata = metadata(None, None);
+        assert_verification_failed(verify(metadata.try_to_vec().unwrap(), &collection_info));
+
+        // Not a metadata account
+        let mut data = account_data(&metadata);
+        data[0] = Key::EditionV1 as u8;
+        assert_verification_failed(verify(data, &collection_info));
+    }
 }
@@ -3,3 +3,20 @@ pub const BATTLE_ROYALE_STATE_SEEDS: &str = "battle-royale-state-seeds";
 pub const BATTLEGROUND_STATE_SEEDS: &str = "battleground-state-seeds";
 pub const BATTLEGROUND_AUTHORITY_SEEDS: &str = "battleground-authority-seeds";
 pub const PARTICIPANT_STATE_SEEDS: &str = "participant-state-seeds";
+
+// Limits
+pub const MAX_PAYOUT_RANKS: usize = 10;
+pub const MAX_STATISTICS: u32 = 100;
+pub const MAX_TEAMS: usize = 4;
+
+// Actions
+pub const POISON_DURATION: i64 = 86400;
+pub const SCOUT_ACTION_POINTS: u32 = 10;
+pub const SCOUT_DURATION: i64 = 3600;
+// Shields are capped at this share of the target's max health points, in basis points
+pub const MAX_SHIELD_RATIO: u32 = 5000;
+pub const SHIELD_DURATION: i64 = 86400;
+
+// Randomness
+// Without a revealed seed by then, the round goes on without critical hits and dodges
+pub const SEED_REVEAL_PERIOD: i64 = 3600;
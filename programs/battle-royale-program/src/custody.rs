@@ -0,0 +1,227 @@
+use crate::errors::*;
+use crate::token_interface::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token;
+use mpl_token_metadata::instruction::{freeze_delegated_account, thaw_delegated_account};
+
+// Soft-staked NFTs stay in the player's wallet, delegated to the battleground authority
+// and frozen by it through the token metadata program, so they can't move during the battle
+#[allow(clippy::too_many_arguments)]
+pub fn stake_nft<'info>(
+    nft_token_account: &AccountInfo<'info>,
+    nft_mint: &AccountInfo<'info>,
+    nft_edition: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_metadata_program: &AccountInfo<'info>,
+    authority_signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    // The token metadata program only freezes accounts of the original token program
+    require!(
+        *nft_token_account.owner == token::ID,
+        BattleRoyaleError::NftNotStakeable
+    );
+
+    token::approve(
+        CpiContext::new(
+            token_program.clone(),
+            token::Approve {
+                to: nft_token_account.clone(),
+                delegate: authority.clone(),
+                authority: owner.clone(),
+            },
+        ),
+        1,
+    )?;
+
+    invoke_signed(
+        &freeze_delegated_account(
+            mpl_token_metadata::id(),
+            authority.key(),
+            nft_token_account.key(),
+            nft_edition.key(),
+            nft_mint.key(),
+        ),
+        &[
+            authority.clone(),
+            nft_token_account.clone(),
+            nft_edition.clone(),
+            nft_mint.clone(),
+            token_program.clone(),
+            token_metadata_program.clone(),
+        ],
+        authority_signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+// Thaw the NFT and revoke the authority's delegation
+// Only the owner can revoke, when they don't sign the delegation is replaced on the next stake
+#[allow(clippy::too_many_arguments)]
+pub fn unstake_nft<'info>(
+    nft_token_account: &AccountInfo<'info>,
+    nft_mint: &AccountInfo<'info>,
+    nft_edition: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_metadata_program: &AccountInfo<'info>,
+    authority_signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &thaw_delegated_account(
+            mpl_token_metadata::id(),
+            authority.key(),
+            nft_token_account.key(),
+            nft_edition.key(),
+            nft_mint.key(),
+        ),
+        &[
+            authority.clone(),
+            nft_token_account.clone(),
+            nft_edition.clone(),
+            nft_mint.clone(),
+            token_program.clone(),
+            token_metadata_program.clone(),
+        ],
+        authority_signer_seeds,
+    )?;
+
+    if !owner.is_signer {
+        return Ok(());
+    }
+    token::revoke(CpiContext::new(
+        token_program.clone(),
+        token::Revoke {
+            source: nft_token_account.clone(),
+            authority: owner.clone(),
+        },
+    ))
+}
+
+// Escrowed NFTs are held in the vault, the associated token account of the battleground authority
+#[allow(clippy::too_many_arguments)]
+pub fn escrow_nft<'info>(
+    nft_token_account: &AccountInfo<'info>,
+    nft_mint: &AccountInfo<'info>,
+    nft_vault: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        nft_vault.key() == associated_token_address(&authority.key(), nft_mint),
+        BattleRoyaleError::InvalidNftTokenAccount
+    );
+    if nft_vault.data_is_empty() {
+        create_associated_token_account(
+            owner,
+            nft_vault,
+            authority,
+            nft_mint,
+            associated_token_program,
+            token_program,
+            system_program,
+            rent,
+        )?;
+    }
+
+    transfer_checked(
+        token_program,
+        nft_token_account,
+        nft_mint,
+        nft_vault,
+        owner,
+        &[],
+        1,
+    )
+}
+
+// Return an escrowed NFT to the associated token account of its owner
+// The emptied vault is closed, its rent goes back to the player who paid for it on join
+#[allow(clippy::too_many_arguments)]
+pub fn return_nft<'info>(
+    nft_vault: &AccountInfo<'info>,
+    nft_mint: &AccountInfo<'info>,
+    owner_nft_token_account: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    rent_receiver: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    authority_signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require!(
+        nft_vault.key() == associated_token_address(&authority.key(), nft_mint)
+            && owner_nft_token_account.key() == associated_token_address(&owner.key(), nft_mint),
+        BattleRoyaleError::InvalidNftTokenAccount
+    );
+    if owner_nft_token_account.data_is_empty() {
+        create_associated_token_account(
+            payer,
+            owner_nft_token_account,
+            owner,
+            nft_mint,
+            associated_token_program,
+            token_program,
+            system_program,
+            rent,
+        )?;
+    }
+
+    transfer_checked(
+        token_program,
+        nft_vault,
+        nft_mint,
+        owner_nft_token_account,
+        authority,
+        authority_signer_seeds,
+        1,
+    )?;
+    close_account(
+        token_program,
+        nft_vault,
+        rent_receiver,
+        authority,
+        authority_signer_seeds,
+    )
+}
+
+// Burn an escrowed NFT and close the emptied vault
+pub fn burn_nft<'info>(
+    nft_vault: &AccountInfo<'info>,
+    nft_mint: &AccountInfo<'info>,
+    rent_receiver: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    authority_signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require!(
+        nft_vault.key() == associated_token_address(&authority.key(), nft_mint),
+        BattleRoyaleError::InvalidNftTokenAccount
+    );
+
+    burn_checked(
+        token_program,
+        nft_vault,
+        nft_mint,
+        authority,
+        authority_signer_seeds,
+        1,
+    )?;
+    close_account(
+        token_program,
+        nft_vault,
+        rent_receiver,
+        authority,
+        authority_signer_seeds,
+    )
+}
@@ -22,4 +22,136 @@ pub enum BattleRoyaleError {
 
     #[msg("Wrong battleground status")]
     WrongBattlegroundStatus,
+
+    #[msg("Battle deadline has not been reached")]
+    DeadlineNotReached,
+
+    #[msg("Battle deadline has passed")]
+    DeadlinePassed,
+
+    #[msg("Invalid surviving participants")]
+    InvalidSurvivors,
+
+    #[msg("Invalid payout table")]
+    InvalidPayoutTable,
+
+    #[msg("No prize to claim")]
+    NoPrizeToClaim,
+
+    #[msg("Prize must be claimed first")]
+    UnclaimedPrize,
+
+    #[msg("Battle can't be started yet")]
+    CannotStartBattle,
+
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+
+    #[msg("Already participating in this round")]
+    AlreadyParticipating,
+
+    #[msg("Participant is not in the current round")]
+    WrongRound,
+
+    #[msg("Invalid pot account")]
+    InvalidPotAccount,
+
+    #[msg("Mint is not owned by a token program")]
+    InvalidMint,
+
+    #[msg("Token program does not own the pot mint")]
+    InvalidTokenProgram,
+
+    #[msg("Token account does not hold the NFT")]
+    InvalidNftTokenAccount,
+
+    #[msg("Statistics are not in the stat table")]
+    StatsVerificationFailed,
+
+    #[msg("No seed was committed for this round")]
+    RandomnessNotCommitted,
+
+    #[msg("The seed of this round is not revealed yet")]
+    RandomnessNotRevealed,
+
+    #[msg("Secret does not match the committed seed")]
+    InvalidSeed,
+
+    #[msg("Action can't be performed on this target")]
+    InvalidTarget,
+
+    #[msg("Target shield is already at its maximum")]
+    ShieldAtMaximum,
+
+    #[msg("Target is already poisoned for all its health points")]
+    PoisonAlreadyLethal,
+
+    #[msg("Next attack must target the scouting participant")]
+    MustAttackScout,
+
+    #[msg("Invalid team")]
+    InvalidTeam,
+
+    #[msg("Friendly fire is disabled")]
+    FriendlyFire,
+
+    #[msg("Target is not alive")]
+    TargetNotAlive,
+
+    #[msg("Target is already at full health")]
+    TargetAtFullHealth,
+
+    #[msg("Heal policy does not allow this target")]
+    HealNotAllowed,
+
+    #[msg("Revives are disabled on this battleground")]
+    RevivesDisabled,
+
+    #[msg("Participant has no revive left")]
+    MaxRevivesReached,
+
+    #[msg("Too few participants remain to revive")]
+    RevivesClosed,
+
+    #[msg("Participant is still alive")]
+    ParticipantAlive,
+
+    #[msg("Battleground has no storm")]
+    StormDisabled,
+
+    #[msg("Participant acted too recently")]
+    ActionOnCooldown,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("Whitelist proof is missing")]
+    WhitelistProofMissing,
+
+    #[msg("Not whitelisted")]
+    NotWhitelisted,
+
+    #[msg("NFT can not be soft-staked")]
+    NftNotStakeable,
+
+    #[msg("NFT was forfeited")]
+    NftForfeited,
+
+    #[msg("NFT is not forfeited")]
+    NftNotForfeited,
+
+    #[msg("Invalid forfeit recipient")]
+    InvalidForfeitRecipient,
+
+    #[msg("Survivor is ranked out of order")]
+    InvalidRanking,
+
+    #[msg("Survivors are not all ranked yet")]
+    RankingIncomplete,
+
+    #[msg("Forfeited NFTs owed to the winner must be claimed first")]
+    UnclaimedForfeits,
+
+    #[msg("Seed reveal period is over")]
+    SeedRevealExpired,
 }
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::state::ActionType;
+use crate::state::{ActionType, PotKind, StartType};
 
 #[event]
 pub struct CreateBattlegroundEvent {
@@ -10,7 +10,10 @@ pub struct CreateBattlegroundEvent {
 #[event]
 pub struct JoinBattlegroundEvent {
     pub battleground: Pubkey,
+    pub round: u32,
     pub nft_mint: Pubkey,
+    pub team: u8,
+    pub pot_kind: PotKind,
     pub attack: u32,
     pub defense: u32,
 }
@@ -19,25 +22,180 @@ pub struct JoinBattlegroundEvent {
 pub struct LeaveBattlegroundEvent {
     pub battleground: Pubkey,
     pub nft_mint: Pubkey,
+    pub pot_kind: PotKind,
+    pub refund: u64,
 }
 
 #[event]
 pub struct StartBattleEvent {
     pub battleground: Pubkey,
+    pub start_type: StartType,
+    pub participants: u32,
+}
+
+#[event]
+pub struct CommitSeedEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub seed_hash: [u8; 32],
+}
+
+#[event]
+pub struct RevealSeedEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub seed: [u8; 32],
 }
 
 #[event]
 pub struct ParticipantActionEvent {
     pub battleground: Pubkey,
     pub participant: Pubkey,
+    pub nft_mint: Pubkey,
+    pub target: Pubkey,
+    pub target_nft_mint: Pubkey,
     pub action_type: ActionType,
     pub action_points_spent: u32,
+    pub nonce: u64,
+    pub roll: Option<[u8; 32]>,
+    pub critical: bool,
+    pub dodged: bool,
+    // Health points of the target around the action
+    pub health_points_before: u32,
+    pub health_points_after: u32,
+    // Damage dealt to the health points, or health points, shield or poison added
+    pub amount: u32,
+    pub killed: bool,
+}
+
+#[event]
+pub struct ParticipantEliminatedEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub nft_mint: Pubkey,
+    pub placement: u32,
+    pub prize: u64,
+}
+
+#[event]
+pub struct ShieldEvent {
+    pub battleground: Pubkey,
+    pub participant: Pubkey,
+    pub target: Pubkey,
+    pub shield: u32,
+    pub shield_until: i64,
+}
+
+#[event]
+pub struct PoisonEvent {
+    pub battleground: Pubkey,
+    pub participant: Pubkey,
+    pub target: Pubkey,
+    pub poison_damage: u32,
+    pub poisoned_until: i64,
+}
+
+#[event]
+pub struct ScoutEvent {
+    pub battleground: Pubkey,
+    pub participant: Pubkey,
+    pub target: Pubkey,
+    pub taunted_until: i64,
+}
+
+#[event]
+pub struct ReviveEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub nft_mint: Pubkey,
+    pub revives: u32,
+    pub health_points: u32,
+    pub pot_kind: PotKind,
+    pub fee: u64,
+}
+
+#[event]
+pub struct ApplyStormEvent {
+    pub battleground: Pubkey,
+    pub nft_mint: Pubkey,
+    pub damage: u32,
+    pub health_points: u32,
+    pub eliminated: bool,
 }
 
 #[event]
 pub struct FinishBattleEvent {
     pub battleground: Pubkey,
+    pub round: u32,
     pub winner: Pubkey,
+    pub pot_kind: PotKind,
     pub pot_mint: Pubkey,
     pub pot_amount: u64,
 }
+
+#[event]
+pub struct FinishBattleByTimeoutEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub survivors: u32,
+}
+
+#[event]
+pub struct RankSurvivorEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub nft_mint: Pubkey,
+    pub health_points: u32,
+}
+
+#[event]
+pub struct SettleWinnerEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub nft_mint: Pubkey,
+    pub pot_kind: PotKind,
+    pub pot_mint: Pubkey,
+    pub prize: u64,
+}
+
+#[event]
+pub struct FinishTeamBattleEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub team: u8,
+}
+
+#[event]
+pub struct ClaimPrizeEvent {
+    pub battleground: Pubkey,
+    pub nft_mint: Pubkey,
+    pub placement: u32,
+    pub pot_kind: PotKind,
+    pub pot_mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CancelBattlegroundEvent {
+    pub battleground: Pubkey,
+    pub participants: u32,
+}
+
+#[event]
+pub struct ClaimRefundEvent {
+    pub battleground: Pubkey,
+    pub nft_mint: Pubkey,
+    pub pot_kind: PotKind,
+    pub pot_mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ForfeitNftEvent {
+    pub battleground: Pubkey,
+    pub round: u32,
+    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    // None when the NFT is burned
+    pub recipient: Option<Pubkey>,
+}
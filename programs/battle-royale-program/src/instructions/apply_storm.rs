@@ -0,0 +1,63 @@
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::ApplyStormEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+// Anyone can resolve the damage taken by an idle participant, and eliminate it
+pub fn apply_storm(ctx: Context<ApplyStorm>) -> Result<()> {
+    // Hazards stop at the deadline, survivors of a timeout are ranked on their health points then
+    let now = ctx.accounts.clock.unix_timestamp;
+    let now = ctx
+        .accounts
+        .battleground
+        .deadline()
+        .map_or(now, |deadline| now.min(deadline));
+    let participant = &mut ctx.accounts.participant;
+    let damage = ctx
+        .accounts
+        .battleground
+        .resolve_hazards(participant, now)?;
+
+    emit!(ApplyStormEvent {
+        battleground: ctx.accounts.battleground.key(),
+        nft_mint: participant.nft_mint,
+        damage,
+        health_points: participant.health_points,
+        eliminated: !participant.alive,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ApplyStorm<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.storm_config.is_some() @ BattleRoyaleError::StormDisabled,
+        constraint = battleground.status == BattlegroundStatus::Ongoing @ BattleRoyaleError::WrongBattlegroundStatus,
+    )]
+    pub battleground: Account<'info, BattlegroundState>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_STATE_SEEDS.as_bytes(),
+            battleground.key().as_ref(),
+            participant.nft_mint.as_ref(),
+        ],
+        bump,
+        constraint = participant.alive @ BattleRoyaleError::TargetNotAlive,
+        constraint = participant.round == battleground.round @ BattleRoyaleError::WrongRound,
+    )]
+    pub participant: Account<'info, ParticipantState>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
@@ -0,0 +1,48 @@
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::CancelBattlegroundEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+pub fn cancel_battleground(ctx: Context<CancelBattleground>) -> Result<()> {
+    ctx.accounts.battleground.status = BattlegroundStatus::Cancelled;
+
+    // Escrowed fees are part of the contributions refunded to the participants
+    ctx.accounts.battleground.escrowed_dev_fees = 0;
+    ctx.accounts.battleground.escrowed_creator_fees = 0;
+
+    emit!(CancelBattlegroundEvent {
+        battleground: ctx.accounts.battleground.key(),
+        participants: ctx.accounts.battleground.participants,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelBattleground<'info> {
+    #[account(
+        constraint = signer.key() == battleground.creator || signer.key() == battle_royale.game_master @ BattleRoyaleError::Unauthorized,
+    )]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Account<'info, BattleRoyaleState>,
+
+    /// The battleground to cancel
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.status == BattlegroundStatus::Preparing @ BattleRoyaleError::WrongBattlegroundStatus,
+    )]
+    pub battleground: Account<'info, BattlegroundState>,
+}
@@ -0,0 +1,214 @@
+use crate::common::*;
+use crate::constants::*;
+use crate::custody::*;
+use crate::errors::*;
+use crate::events::ForfeitNftEvent;
+use crate::state::*;
+use crate::token_interface::*;
+use anchor_lang::prelude::*;
+use anchor_lang::AccountsClose;
+use anchor_spl::associated_token::*;
+
+// Remaining accounts layout:
+// - the state of a winner of the round, writable, if forfeited NFTs go to the winners
+// - then, for each forfeited participant, its state, its owner, its NFT mint, the vault holding
+//   the NFT and, unless the NFT is burned, the recipient's NFT token account
+pub fn claim_forfeited_nfts<'info>(
+    ctx: Context<'_, '_, '_, 'info, ClaimForfeitedNfts<'info>>,
+) -> Result<()> {
+    let battleground_key = ctx.accounts.battleground.key();
+    let mut forfeit_recipient = ctx
+        .accounts
+        .battleground
+        .custody_config
+        .forfeit_recipient
+        .clone()
+        .ok_or(BattleRoyaleError::NftNotForfeited)?;
+
+    // NFTs forfeited in a round without winners go to the creator, before any winner claims again
+    let winnerless = ctx.accounts.battleground.winnerless_forfeits > 0;
+    if winnerless {
+        forfeit_recipient = ForfeitRecipient::Creator;
+    }
+
+    // Forfeited NFTs go to the player of a winner, to the creator, or nowhere when burned
+    // Winners claim the forfeited NFTs of their round they are owed, see `owe_forfeits`
+    let mut remaining_accounts = ctx.remaining_accounts;
+    let mut winner = None;
+    let recipient = match forfeit_recipient {
+        ForfeitRecipient::Winner => {
+            let winner_info = remaining_accounts
+                .first()
+                .ok_or(BattleRoyaleError::InvalidForfeitRecipient)?;
+            remaining_accounts = &remaining_accounts[1..];
+            let winner = winner.insert(Account::<ParticipantState>::try_from(winner_info)?);
+            require!(
+                winner.battleground == battleground_key
+                    && winner.round < ctx.accounts.battleground.round,
+                BattleRoyaleError::InvalidForfeitRecipient
+            );
+            Some(winner.owner)
+        }
+        ForfeitRecipient::Creator => Some(ctx.accounts.battleground.creator),
+        ForfeitRecipient::Burn => None,
+    };
+    if let Some(recipient) = recipient {
+        require!(
+            ctx.accounts.recipient.key() == recipient,
+            BattleRoyaleError::InvalidForfeitRecipient
+        );
+    }
+
+    let accounts_per_nft = if recipient.is_some() { 5 } else { 4 };
+    require!(
+        !remaining_accounts.is_empty() && remaining_accounts.len().is_multiple_of(accounts_per_nft),
+        BattleRoyaleError::NftNotForfeited
+    );
+    let claimed = (remaining_accounts.len() / accounts_per_nft) as u32;
+    let battleground = &mut ctx.accounts.battleground;
+    let claimed_round = match winner.as_mut() {
+        Some(winner) => {
+            require!(
+                winner.forfeits_owed >= claimed,
+                BattleRoyaleError::InvalidForfeitRecipient
+            );
+            winner.forfeits_owed -= claimed;
+            Some(winner.round)
+        }
+        None if winnerless => {
+            require!(
+                battleground.winnerless_forfeits >= claimed,
+                BattleRoyaleError::NftNotForfeited
+            );
+            battleground.winnerless_forfeits -= claimed;
+            Some(battleground.round.safe_sub(1)?)
+        }
+        None => None,
+    };
+
+    // Get authority signer seeds
+    let authority_bump = *ctx.bumps.get("authority").unwrap();
+    let authority_seeds = &[
+        BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+        &ctx.accounts.battleground.id.to_le_bytes(),
+        &[authority_bump],
+    ];
+    let authority_signer_seeds = &[&authority_seeds[..]];
+
+    for accounts in remaining_accounts.chunks(accounts_per_nft) {
+        let participant = Account::<ParticipantState>::try_from(&accounts[0])?;
+        let owner = &accounts[1];
+        let nft_mint = &accounts[2];
+        let nft_vault = &accounts[3];
+
+        // Only NFTs of participants eliminated in a finished round are forfeited
+        // Their prize has to be claimed first, as the participant account is closed
+        require!(
+            participant.battleground == battleground_key
+                && participant.round < ctx.accounts.battleground.round
+                && ctx.accounts.battleground.is_forfeited(&participant)
+                && claimed_round.unwrap_or(participant.round) == participant.round,
+            BattleRoyaleError::NftNotForfeited
+        );
+        require!(participant.prize == 0, BattleRoyaleError::UnclaimedPrize);
+        require!(
+            owner.key() == participant.owner,
+            BattleRoyaleError::InvalidNftTokenAccount
+        );
+        require!(
+            nft_mint.key() == participant.nft_mint
+                && *nft_mint.owner == ctx.accounts.nft_token_program.key(),
+            BattleRoyaleError::InvalidMint
+        );
+
+        match recipient {
+            Some(_) => return_nft(
+                nft_vault,
+                nft_mint,
+                &accounts[4],
+                &ctx.accounts.recipient,
+                &ctx.accounts.signer,
+                owner,
+                &ctx.accounts.authority,
+                &ctx.accounts.nft_token_program,
+                &ctx.accounts.associated_token_program.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent.to_account_info(),
+                authority_signer_seeds,
+            )?,
+            None => burn_nft(
+                nft_vault,
+                nft_mint,
+                owner,
+                &ctx.accounts.authority,
+                &ctx.accounts.nft_token_program,
+                authority_signer_seeds,
+            )?,
+        }
+
+        emit!(ForfeitNftEvent {
+            battleground: battleground_key,
+            round: participant.round,
+            nft_mint: participant.nft_mint,
+            owner: participant.owner,
+            recipient,
+        });
+
+        // The participant is settled, its rent goes back to its owner
+        participant.close(owner.clone())?;
+    }
+
+    if let Some(winner) = winner {
+        winner.exit(ctx.program_id)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimForfeitedNfts<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
+
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// The hardcore battleground the NFTs were forfeited in
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+
+    /// The player receiving the forfeited NFTs
+    /// CHECK: Verified against the battleground forfeit recipient, unused when burning
+    pub recipient: UncheckedAccount<'info>,
+
+    // Solana ecosystem program addresses
+    /// CHECK: Either token program, the one owning the NFT mints
+    #[account(constraint = is_token_program(&nft_token_program.key()) @ BattleRoyaleError::InvalidTokenProgram)]
+    pub nft_token_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
@@ -0,0 +1,159 @@
+use crate::common::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::ClaimPrizeEvent;
+use crate::pot::*;
+use crate::state::*;
+use crate::token_interface::*;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::*;
+
+pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+    let prize = ctx.accounts.participant.prize;
+
+    // Get authority signer seeds
+    let authority_bump = *ctx.bumps.get("authority").unwrap();
+    let authority_seeds = &[
+        BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+        &ctx.accounts.battleground.id.to_le_bytes(),
+        &[authority_bump],
+    ];
+    let authority_signer_seeds = &[&authority_seeds[..]];
+
+    // Transfer the prize to the participant
+    let battleground = &ctx.accounts.battleground;
+    let signer = ctx.accounts.signer.to_account_info();
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.authority.key(),
+        &ctx.accounts.pot_account,
+    )?;
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &signer.key(),
+        &ctx.accounts.player_account,
+    )?;
+
+    create_pot_account_if_needed(
+        battleground,
+        &signer,
+        &ctx.accounts.player_account,
+        &signer,
+        &ctx.accounts.pot_mint.to_account_info(),
+        &ctx.accounts.associated_token_program.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent.to_account_info(),
+    )?;
+    transfer_pot_currency(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.pot_account,
+        &ctx.accounts.player_account,
+        &ctx.accounts.authority,
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        authority_signer_seeds,
+        prize,
+    )?;
+
+    ctx.accounts.participant.prize = 0;
+    ctx.accounts.battleground.unclaimed_prizes =
+        ctx.accounts.battleground.unclaimed_prizes.safe_sub(prize)?;
+
+    emit!(ClaimPrizeEvent {
+        battleground: ctx.accounts.battleground.key(),
+        nft_mint: ctx.accounts.nft_mint.key(),
+        placement: ctx.accounts.participant.placement,
+        pot_kind: ctx.accounts.battleground.pot_kind.clone(),
+        pot_mint: ctx.accounts.battleground.pot_mint,
+        amount: prize,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The Battle Royale state
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
+
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// The battleground the participant was ranked in
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = pot_mint,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+
+    /// The participant state
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_STATE_SEEDS.as_bytes(),
+            battleground.key().as_ref(),
+            nft_mint.key().as_ref(),
+        ],
+        bump,
+        has_one = nft_mint,
+        constraint = participant.prize > 0 @ BattleRoyaleError::NoPrizeToClaim,
+    )]
+    pub participant: Box<Account<'info, ParticipantState>>,
+
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
+
+    /// The NFT used to participate
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&nft_mint) @ BattleRoyaleError::InvalidMint)]
+    pub nft_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub pot_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub player_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token account of either token program holding the NFT
+    #[account(
+        constraint = participant.is_controlled_by(&signer.key(), &player_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
+    )]
+    pub player_nft_token_account: UncheckedAccount<'info>,
+
+    // Solana ecosystem program addresses
+    /// CHECK: Either token program, the one owning the pot mint
+    #[account(constraint = token_program.key() == *pot_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
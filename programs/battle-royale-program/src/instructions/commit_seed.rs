@@ -0,0 +1,45 @@
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::CommitSeedEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+pub fn commit_seed(ctx: Context<CommitSeed>, seed_hash: [u8; 32]) -> Result<()> {
+    ctx.accounts.battleground.seed_hash = Some(seed_hash);
+
+    emit!(CommitSeedEvent {
+        battleground: ctx.accounts.battleground.key(),
+        round: ctx.accounts.battleground.round,
+        seed_hash,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CommitSeed<'info> {
+    #[account(
+        constraint = signer.key() == battleground.creator || signer.key() == battle_royale.game_master @ BattleRoyaleError::Unauthorized,
+    )]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Account<'info, BattleRoyaleState>,
+
+    /// The battleground of the round, the seed is committed before the battle starts
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.status == BattlegroundStatus::Preparing @ BattleRoyaleError::WrongBattlegroundStatus,
+    )]
+    pub battleground: Account<'info, BattlegroundState>,
+}
@@ -3,9 +3,10 @@ use crate::constants::*;
 use crate::errors::*;
 use crate::events::*;
 use crate::state::*;
+use crate::token_interface::*;
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use anchor_spl::token;
-use anchor_spl::token::*;
 
 pub fn create_battleground(
     ctx: Context<CreateBattleground>,
@@ -14,35 +15,135 @@ pub fn create_battleground(
     entry_fee: u64,
     creator: Pubkey,
     creator_fee: u16,
-    action_points_per_day: u32,
-    whitelist_root: Option<[u8; 32]>,
+    params: BattlegroundParams,
 ) -> Result<()> {
+    let BattlegroundParams {
+        action_points_per_day,
+        whitelist_root,
+        max_duration,
+        payout_table,
+        min_participants,
+        scheduled_start,
+        fees_refundable,
+        champion_free_entry,
+        pot_kind,
+        stats_source,
+        combat_config,
+        team_config,
+        revive_config,
+        storm_config,
+        action_config,
+        custody_config,
+    } = params;
+
+    let combat_config = combat_config.unwrap_or_default();
     require!(
-        creator_fee + ctx.accounts.battle_royale.fee < 10000,
+        combat_config.is_valid(),
         BattleRoyaleError::InvalidParameter
     );
 
-    match collection_info {
-        CollectionInfo::V1 {
-            ref symbol,
-            ref verified_creators,
-            whitelist_root: _,
-        } => {
-            // Check if symbol is too long
-            require!(
-                // Max string length is 8, so UTF-8 encoded max byte length is 32
-                symbol.len() <= 8 * 4,
-                BattleRoyaleError::CollectionSymbolInvalid
-            );
+    require!(
+        (creator_fee as u32 + ctx.accounts.battle_royale.fee as u32) < 10000,
+        BattleRoyaleError::InvalidParameter
+    );
 
-            // Check if there are 1-5 verified creators
+    require!(
+        max_duration.is_none() || max_duration.unwrap() > 0,
+        BattleRoyaleError::InvalidParameter
+    );
+
+    require!(
+        min_participants > 0 && min_participants <= participants_cap,
+        BattleRoyaleError::InvalidParameter
+    );
+
+    // An empty payout table means the winner takes the whole pot
+    let payout_table = if payout_table.is_empty() {
+        vec![10000 - creator_fee - ctx.accounts.battle_royale.fee]
+    } else {
+        payout_table
+    };
+    require!(
+        payout_table.len() <= MAX_PAYOUT_RANKS
+            && payout_table.iter().map(|bps| *bps as u32).sum::<u32>()
+                + creator_fee as u32
+                + ctx.accounts.battle_royale.fee as u32
+                == 10000,
+        BattleRoyaleError::InvalidPayoutTable
+    );
+
+    require!(
+        revive_config.is_none() || revive_config.as_ref().unwrap().is_valid(payout_table.len()),
+        BattleRoyaleError::InvalidParameter
+    );
+
+    require!(
+        storm_config.is_none() || storm_config.as_ref().unwrap().is_valid(),
+        BattleRoyaleError::InvalidParameter
+    );
+
+    let action_config = action_config.unwrap_or_default();
+    require!(
+        action_config.is_valid(),
+        BattleRoyaleError::InvalidParameter
+    );
+
+    let custody_config = custody_config.unwrap_or_default();
+    require!(
+        custody_config.is_valid(),
+        BattleRoyaleError::InvalidParameter
+    );
+
+    // Native pots keep the native mint as their pot mint, and the authority as their vault
+    if pot_kind == PotKind::Native {
+        require!(
+            ctx.accounts.pot_mint.key() == token::spl_token::native_mint::ID,
+            BattleRoyaleError::InvalidParameter
+        );
+
+        // Fund the vault so that it stays rent exempt whatever the pot amount
+        let vault_rent = Rent::get()?.minimum_balance(0);
+        let vault_lamports = ctx.accounts.authority.lamports();
+        if vault_lamports < vault_rent {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info().clone(),
+                    system_program::Transfer {
+                        from: ctx.accounts.signer.to_account_info().clone(),
+                        to: ctx.accounts.authority.to_account_info().clone(),
+                    },
+                ),
+                vault_rent - vault_lamports,
+            )?;
+        }
+    }
+
+    verify_collection_info(&collection_info)?;
+
+    // Team battles are fought by 2 to MAX_TEAMS teams
+    let team_config = team_config.unwrap_or_default();
+    match team_config.team_mode {
+        TeamMode::FreeForAll => {}
+        TeamMode::ByCollection { ref collections } => {
             require!(
-                !verified_creators.is_empty() && verified_creators.len() <= 5,
-                BattleRoyaleError::VerifiedCreatorsInvalid
-            )
+                collections.len() >= 2 && collections.len() <= MAX_TEAMS,
+                BattleRoyaleError::InvalidParameter
+            );
+            for collection_info in collections.iter() {
+                verify_collection_info(collection_info)?;
+            }
         }
-        CollectionInfo::V2 { collection_mint: _ } => {}
-    };
+        TeamMode::PlayerChoice { teams } => require!(
+            teams >= 2 && teams as usize <= MAX_TEAMS,
+            BattleRoyaleError::InvalidParameter
+        ),
+    }
+    let team_survivors = vec![0; team_config.team_mode.team_count()];
+    require!(
+        team_config.heal_policy != HealPolicy::TeamOnly
+            || team_config.team_mode != TeamMode::FreeForAll,
+        BattleRoyaleError::InvalidParameter
+    );
 
     // Initialize the battleground account
     *ctx.accounts.battleground = BattlegroundState {
@@ -60,9 +161,38 @@ pub fn create_battleground(
         creator_fee,
         last_winner: None,
         whitelist_root,
+        max_duration,
+        payout_table,
+        prize_pool: 0,
+        unclaimed_prizes: 0,
+        min_participants,
+        scheduled_start,
+        fees_refundable,
+        escrowed_dev_fees: 0,
+        escrowed_creator_fees: 0,
+        round: 0,
+        champion_free_entry,
+        pot_kind,
+        stats_source,
+        combat_config,
+        seed_hash: None,
+        seed: None,
+        team_config,
+        team_survivors,
+        revive_config,
+        storm_config,
+        action_config,
+        custody_config,
+        forfeited_nfts: 0,
+        winnerless_forfeits: 0,
+        settlement: Settlement::default(),
     };
 
-    ctx.accounts.battle_royale.last_battleground_id += 1;
+    ctx.accounts.battle_royale.last_battleground_id = ctx
+        .accounts
+        .battle_royale
+        .last_battleground_id
+        .safe_add(1)?;
 
     emit!(CreateBattlegroundEvent {
         battleground: ctx.accounts.battleground.key()
@@ -71,6 +201,31 @@ pub fn create_battleground(
     Ok(())
 }
 
+fn verify_collection_info(collection_info: &CollectionInfo) -> Result<()> {
+    match collection_info {
+        CollectionInfo::V1 {
+            symbol,
+            verified_creators,
+            whitelist_root: _,
+        } => {
+            // Check if symbol is too long
+            require!(
+                // Max string length is 8, so UTF-8 encoded max byte length is 32
+                symbol.len() <= 8 * 4,
+                BattleRoyaleError::CollectionSymbolInvalid
+            );
+
+            // Check if there are 1-5 verified creators
+            require!(
+                !verified_creators.is_empty() && verified_creators.len() <= 5,
+                BattleRoyaleError::VerifiedCreatorsInvalid
+            );
+        }
+        CollectionInfo::V2 { collection_mint: _ } => {}
+    };
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct CreateBattleground<'info> {
     /// The signer that will create the battleground
@@ -87,9 +242,10 @@ pub struct CreateBattleground<'info> {
     )]
     pub battle_royale: Account<'info, BattleRoyaleState>,
 
-    /// The authority that holds the pot
+    /// The authority that holds the pot, and the vault of native pots
     /// CHECK: Checking correspondance with battle royale state
     #[account(
+        mut,
         seeds = [
             BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
             battle_royale.last_battleground_id.to_le_bytes().as_ref()
@@ -112,8 +268,9 @@ pub struct CreateBattleground<'info> {
     pub battleground: Account<'info, BattlegroundState>,
 
     /// The mint of the token used to pay the entry fee
-    #[account(owner = token::ID)]
-    pub pot_mint: Account<'info, Mint>,
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
 
     /// Solana ecosystem program addresses
     pub system_program: Program<'info, System>,
@@ -1,20 +1,22 @@
+use crate::common::*;
 use crate::constants::*;
+use crate::custody::*;
 use crate::errors::*;
 use crate::events::FinishBattleEvent;
+use crate::pot::*;
 use crate::state::*;
+use crate::token_interface::*;
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::*;
-use anchor_spl::token;
-use anchor_spl::token::*;
 
 pub fn finish_battle(ctx: Context<FinishBattle>) -> Result<()> {
-    // Reset the battleground
-    ctx.accounts.battleground.status = BattlegroundStatus::Preparing;
-    ctx.accounts.battleground.last_winner = Some(ctx.accounts.winner_nft_token_account.mint);
-
-    // Reset the participant
-    ctx.accounts.participant.action_points_spent = 0;
-    ctx.accounts.participant.health_points = 750 + (ctx.accounts.participant.defense + 50) * 5;
+    // Reset the battleground for the next round, the winner has to join again
+    let round = ctx.accounts.battleground.round;
+    ctx.accounts
+        .battleground
+        .owe_forfeits(&mut ctx.accounts.participant, 1)?;
+    ctx.accounts.battleground.last_winner = Some(ctx.accounts.nft_mint.key());
+    ctx.accounts.battleground.reset_round()?;
 
     // Get authority signer seeds
     let authority_bump = *ctx.bumps.get("authority").unwrap();
@@ -25,23 +27,85 @@ pub fn finish_battle(ctx: Context<FinishBattle>) -> Result<()> {
     ];
     let authority_signer_seeds = &[&authority_seeds[..]];
 
+    // The winner gets everything that is not owed to other ranked participants
+    let battleground = &ctx.accounts.battleground;
+    let winner = ctx.accounts.winner.to_account_info();
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.authority.key(),
+        &ctx.accounts.pot_account,
+    )?;
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &winner.key(),
+        &ctx.accounts.winner_account,
+    )?;
+    let winner_prize = pot_balance(battleground, &ctx.accounts.pot_account)?
+        .safe_sub(battleground.unclaimed_prizes)?;
+
     // Transfer the pot to the winner
-    let transfer_nft_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info().clone(),
-        token::Transfer {
-            from: ctx.accounts.pot_account.to_account_info().clone(),
-            to: ctx.accounts.winner_account.to_account_info().clone(),
-            authority: ctx.accounts.authority.to_account_info().clone(),
-        },
+    create_pot_account_if_needed(
+        battleground,
+        &ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.winner_account,
+        &winner,
+        &ctx.accounts.pot_mint.to_account_info(),
+        &ctx.accounts.associated_token_program.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent.to_account_info(),
+    )?;
+    transfer_pot_currency(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.pot_account,
+        &ctx.accounts.winner_account,
+        &ctx.accounts.authority,
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
         authority_signer_seeds,
-    );
-    token::transfer(transfer_nft_ctx, ctx.accounts.pot_account.amount)?;
+        winner_prize,
+    )?;
+
+    // Release the winner's NFT, it is staked or escrowed again if they join the next round
+    match ctx.accounts.participant.nft_custody {
+        NftCustody::Wallet => (),
+        NftCustody::SoftStake => unstake_nft(
+            &ctx.accounts.winner_nft_token_account,
+            &ctx.accounts.nft_mint,
+            &ctx.accounts.nft_edition,
+            &winner,
+            &ctx.accounts.authority,
+            &ctx.accounts.nft_token_program,
+            &ctx.accounts.token_metadata_program,
+            authority_signer_seeds,
+        )?,
+        NftCustody::Escrow => return_nft(
+            &ctx.accounts.nft_vault,
+            &ctx.accounts.nft_mint,
+            &ctx.accounts.winner_nft_token_account,
+            &winner,
+            &ctx.accounts.signer.to_account_info(),
+            &winner,
+            &ctx.accounts.authority,
+            &ctx.accounts.nft_token_program,
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            authority_signer_seeds,
+        )?,
+    }
+    ctx.accounts.participant.nft_custody = NftCustody::Wallet;
 
     emit!(FinishBattleEvent {
         battleground: ctx.accounts.battleground.key(),
+        round,
         winner: ctx.accounts.participant.key(),
+        pot_kind: ctx.accounts.battleground.pot_kind.clone(),
         pot_mint: ctx.accounts.battleground.pot_mint,
-        pot_amount: ctx.accounts.pot_account.amount,
+        pot_amount: winner_prize,
     });
 
     Ok(())
@@ -53,6 +117,7 @@ pub struct FinishBattle<'info> {
     pub signer: Signer<'info>,
 
     /// CHECK: We check that this account owns the token
+    #[account(mut)]
     pub winner: UncheckedAccount<'info>,
 
     #[account(
@@ -65,6 +130,7 @@ pub struct FinishBattle<'info> {
 
     /// CHECK: Checking correspondance with battle royale state
     #[account(
+        mut,
         seeds = [
             BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
             battleground.id.to_le_bytes().as_ref(),
@@ -97,40 +163,52 @@ pub struct FinishBattle<'info> {
         bump,
         has_one = nft_mint,
         constraint = participant.alive,
+        constraint = participant.round == battleground.round @ BattleRoyaleError::WrongRound,
     )]
     pub participant: Box<Account<'info, ParticipantState>>,
 
-    #[account(owner = token::ID)]
-    pub pot_mint: Account<'info, Mint>,
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
 
-    #[account(owner = token::ID)]
-    pub nft_mint: Account<'info, Mint>,
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&nft_mint) @ BattleRoyaleError::InvalidMint)]
+    pub nft_mint: UncheckedAccount<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = pot_mint,
-        associated_token::authority = authority,
-    )]
-    pub pot_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub pot_account: UncheckedAccount<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = pot_mint,
-        associated_token::authority = winner,
-    )]
-    pub winner_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub winner_account: UncheckedAccount<'info>,
 
+    /// CHECK: Token account of either token program holding the NFT
     #[account(
-        associated_token::mint = nft_mint,
-        associated_token::authority = winner,
-        constraint = winner_nft_token_account.amount == 1,
+        mut,
+        constraint = participant.is_controlled_by(&winner.key(), &winner_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
     )]
-    pub winner_nft_token_account: Box<Account<'info, TokenAccount>>,
+    pub winner_nft_token_account: UncheckedAccount<'info>,
+
+    /// The master edition, freeze authority of soft-staked NFTs
+    /// CHECK: Verified by the token metadata program
+    pub nft_edition: UncheckedAccount<'info>,
+
+    /// The authority's token account holding escrowed NFTs
+    /// CHECK: Verified against the authority's associated token account
+    #[account(mut)]
+    pub nft_vault: UncheckedAccount<'info>,
 
     // Solana ecosystem program addresses
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Either token program, the one owning the pot mint
+    #[account(constraint = token_program.key() == *pot_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: Either token program, the one owning the NFT mint
+    #[account(constraint = nft_token_program.key() == *nft_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub nft_token_program: UncheckedAccount<'info>,
+    /// CHECK: The token metadata program
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -0,0 +1,53 @@
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::FinishBattleByTimeoutEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+// Survivors are then ranked by `rank_survivor` and paid by `settle_survivor`, one per instruction
+pub fn finish_battle_by_timeout(ctx: Context<FinishBattleByTimeout>) -> Result<()> {
+    let battleground_key = ctx.accounts.battleground.key();
+    let battleground = &mut ctx.accounts.battleground;
+    battleground.status = BattlegroundStatus::Finished;
+
+    emit!(FinishBattleByTimeoutEvent {
+        battleground: battleground_key,
+        round: battleground.round,
+        survivors: battleground.participants,
+    });
+
+    // Without survivors there is nobody to settle
+    if battleground.participants == 0 {
+        battleground.reset_round()?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FinishBattleByTimeout<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
+
+    /// The battleground that reached its deadline
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.status == BattlegroundStatus::Ongoing @ BattleRoyaleError::WrongBattlegroundStatus,
+        constraint = battleground.deadline().is_some() && battleground.deadline().unwrap() <= clock.unix_timestamp @ BattleRoyaleError::DeadlineNotReached,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
@@ -0,0 +1,52 @@
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::FinishTeamBattleEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+// The surviving team members are then paid by `settle_survivor`, one per instruction
+pub fn finish_team_battle(ctx: Context<FinishTeamBattle>) -> Result<()> {
+    let battleground_key = ctx.accounts.battleground.key();
+    let battleground = &mut ctx.accounts.battleground;
+    let team = battleground
+        .last_team_standing()
+        .ok_or(BattleRoyaleError::InvalidSurvivors)?;
+    battleground.status = BattlegroundStatus::Finished;
+    battleground.settlement.ranked = battleground.participants;
+    battleground.settlement.winning_teams = 1 << team;
+    battleground.settlement.winners = battleground.participants;
+
+    emit!(FinishTeamBattleEvent {
+        battleground: battleground_key,
+        round: battleground.round,
+        team,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FinishTeamBattle<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
+
+    /// The team battleground with a single team left
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.is_team_battle() @ BattleRoyaleError::InvalidTeam,
+        constraint = battleground.status == BattlegroundStatus::Ongoing @ BattleRoyaleError::WrongBattlegroundStatus,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+}
@@ -1,79 +1,268 @@
 use crate::common::*;
 use crate::constants::*;
+use crate::custody::*;
 use crate::errors::*;
 use crate::events::*;
+use crate::pot::*;
 use crate::state::*;
+use crate::token_interface::*;
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::*;
-use anchor_spl::token;
-use anchor_spl::token::*;
 
 pub fn join_battleground(
     ctx: Context<JoinBattleground>,
     attack: u32,
     defense: u32,
-    _collection_whitelist_proof: Option<Vec<[u8; 32]>>,
-    _holder_whitelist_proof: Option<Vec<[u8; 32]>>,
+    collection_whitelist_proof: Option<Vec<[u8; 32]>>,
+    holder_whitelist_proof: Option<Vec<[u8; 32]>>,
+    stats_proof: Option<Vec<[u8; 32]>>,
+    team: u8,
 ) -> Result<()> {
-    require!(
-        attack + defense <= 100,
-        BattleRoyaleError::InvalidStatistics
-    );
+    // Whitelists are enforced whenever the battleground has one
+    let nft_mint = ctx.accounts.nft_mint.key();
+    require_whitelisted(
+        ctx.accounts
+            .battleground
+            .team_collection(team)
+            .whitelist_root(),
+        collection_whitelist_proof,
+        &nft_mint,
+    )?;
+    require_whitelisted(
+        ctx.accounts.battleground.whitelist_root,
+        holder_whitelist_proof,
+        &ctx.accounts.signer.key(),
+    )?;
 
-    *ctx.accounts.participant = ParticipantState {
-        bump: *ctx.bumps.get("participant").unwrap(),
-        battleground: ctx.accounts.battleground.key(),
-        nft_mint: ctx.accounts.nft_mint.key(),
-        attack: attack + 100,
-        defense: defense + 50,
-        action_points_spent: 0,
-        health_points: 750 + (defense + 50) * 5,
-        alive: true,
+    // Statistics are either declared by the player, or derived from the NFT
+    let combat_config = ctx.accounts.battleground.combat_config.clone();
+    let max_statistics = combat_config.max_statistics;
+    let (attack, defense) = match ctx.accounts.battleground.stats_source {
+        StatsSource::Declared => {
+            require!(
+                attack.safe_add(defense)? <= max_statistics,
+                BattleRoyaleError::InvalidStatistics
+            );
+            (attack, defense)
+        }
+        StatsSource::StatTable { root } => {
+            require!(
+                attack <= max_statistics && defense <= max_statistics,
+                BattleRoyaleError::InvalidStatistics
+            );
+            require!(
+                stats_proof.is_some()
+                    && verify_proof(
+                        stats_proof.unwrap(),
+                        root,
+                        stat_table_leaf(&nft_mint, attack, defense)
+                    ),
+                BattleRoyaleError::StatsVerificationFailed
+            );
+            (attack, defense)
+        }
+        StatsSource::MintHash => mint_hash_statistics(&nft_mint, max_statistics),
+    };
+
+    // The defending champion re-enters for free if the creator opted in
+    let entry_fee = if ctx.accounts.battleground.champion_free_entry
+        && ctx.accounts.battleground.last_winner == Some(nft_mint)
+    {
+        0
+    } else {
+        ctx.accounts.battleground.entry_fee
     };
-    ctx.accounts.battleground.participants += 1;
+    let dev_fee = basis_points_of(entry_fee, ctx.accounts.battle_royale.fee)?;
+    let creator_fee = basis_points_of(entry_fee, ctx.accounts.battleground.creator_fee)?;
 
-    let entry_fee = ctx.accounts.battleground.entry_fee;
-    let dev_fee = entry_fee * (ctx.accounts.battle_royale.fee as u64) / 10000;
-    let creator_fee = entry_fee * (ctx.accounts.battleground.creator_fee as u64) / 10000;
+    // Refundable fees are escrowed in the pot until the battle starts
+    let fees_refundable = ctx.accounts.battleground.fees_refundable;
+    let pot_fee = if fees_refundable {
+        entry_fee
+    } else {
+        entry_fee.safe_sub(dev_fee)?.safe_sub(creator_fee)?
+    };
 
     msg!(
         "Paying {} to the pot, {} to the treasury",
-        entry_fee - dev_fee,
+        entry_fee.safe_sub(dev_fee)?,
         dev_fee
     );
 
+    // Check the pot currency accounts, and create the missing ones
+    let battleground = &ctx.accounts.battleground;
+    let signer = ctx.accounts.signer.to_account_info();
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &signer.key(),
+        &ctx.accounts.player_account,
+    )?;
+    for (account, owner) in [
+        (&ctx.accounts.pot_account, &ctx.accounts.authority),
+        (&ctx.accounts.dev_account, &ctx.accounts.dev_fund),
+        (&ctx.accounts.creator_account, &ctx.accounts.creator),
+    ] {
+        verify_pot_account(battleground, &ctx.accounts.pot_mint, &owner.key(), account)?;
+        create_pot_account_if_needed(
+            battleground,
+            &signer,
+            account,
+            owner,
+            &ctx.accounts.pot_mint.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &token_program,
+            &system_program,
+            &ctx.accounts.rent.to_account_info(),
+        )?;
+    }
+
     // Pay the ticket price
-    let transfer_entry_fee_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info().clone(),
-        token::Transfer {
-            from: ctx.accounts.player_account.to_account_info().clone(),
-            to: ctx.accounts.pot_account.to_account_info().clone(),
-            authority: ctx.accounts.signer.to_account_info().clone(),
-        },
-    );
-    token::transfer(transfer_entry_fee_ctx, entry_fee - dev_fee - creator_fee)?;
-    let transfer_dev_fee_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info().clone(),
-        token::Transfer {
-            from: ctx.accounts.player_account.to_account_info().clone(),
-            to: ctx.accounts.dev_account.to_account_info().clone(),
-            authority: ctx.accounts.signer.to_account_info().clone(),
-        },
-    );
-    token::transfer(transfer_dev_fee_ctx, dev_fee)?;
-    let transfer_creator_fee_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info().clone(),
-        token::Transfer {
-            from: ctx.accounts.player_account.to_account_info().clone(),
-            to: ctx.accounts.creator_account.to_account_info().clone(),
-            authority: ctx.accounts.signer.to_account_info().clone(),
-        },
-    );
-    token::transfer(transfer_creator_fee_ctx, creator_fee)?;
+    let pot_balance_before = pot_balance(battleground, &ctx.accounts.pot_account)?;
+    transfer_pot_currency(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.player_account,
+        &ctx.accounts.pot_account,
+        &signer,
+        &token_program,
+        &system_program,
+        &[],
+        pot_fee,
+    )?;
+    if !fees_refundable {
+        transfer_pot_currency(
+            battleground,
+            &ctx.accounts.pot_mint,
+            &ctx.accounts.player_account,
+            &ctx.accounts.dev_account,
+            &signer,
+            &token_program,
+            &system_program,
+            &[],
+            dev_fee,
+        )?;
+        transfer_pot_currency(
+            battleground,
+            &ctx.accounts.pot_mint,
+            &ctx.accounts.player_account,
+            &ctx.accounts.creator_account,
+            &signer,
+            &token_program,
+            &system_program,
+            &[],
+            creator_fee,
+        )?;
+    }
+
+    // Mints with a transfer fee withhold part of what is sent, only account for what the pot received
+    let received =
+        pot_balance(battleground, &ctx.accounts.pot_account)?.safe_sub(pot_balance_before)?;
+    let (escrowed_dev_fee, escrowed_creator_fee) = if fees_refundable {
+        (
+            received_share(dev_fee, pot_fee, received),
+            received_share(creator_fee, pot_fee, received),
+        )
+    } else {
+        (0, 0)
+    };
+    let contribution = received
+        .safe_sub(escrowed_dev_fee)?
+        .safe_sub(escrowed_creator_fee)?;
+    let battleground = &mut ctx.accounts.battleground;
+    battleground.escrowed_dev_fees = battleground.escrowed_dev_fees.safe_add(escrowed_dev_fee)?;
+    battleground.escrowed_creator_fees = battleground
+        .escrowed_creator_fees
+        .safe_add(escrowed_creator_fee)?;
+
+    let attack = combat_config.base_attack.safe_add(attack)?;
+    let defense = combat_config.base_defense.safe_add(defense)?;
+
+    // NFTs kept in custody since a previous round are already frozen or escrowed
+    let nft_custody = ctx.accounts.battleground.custody_config.nft_custody.clone();
+    if ctx.accounts.participant.nft_custody == NftCustody::Wallet {
+        match nft_custody {
+            NftCustody::Wallet => (),
+            NftCustody::SoftStake => {
+                let authority_bump = *ctx.bumps.get("authority").unwrap();
+                let authority_seeds = &[
+                    BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+                    &ctx.accounts.battleground.id.to_le_bytes(),
+                    &[authority_bump],
+                ];
+                stake_nft(
+                    &ctx.accounts.player_nft_token_account,
+                    &ctx.accounts.nft_mint,
+                    &ctx.accounts.nft_edition,
+                    &ctx.accounts.signer,
+                    &ctx.accounts.authority,
+                    &ctx.accounts.nft_token_program,
+                    &ctx.accounts.token_metadata_program,
+                    &[&authority_seeds[..]],
+                )?;
+            }
+            NftCustody::Escrow => escrow_nft(
+                &ctx.accounts.player_nft_token_account,
+                &ctx.accounts.nft_mint,
+                &ctx.accounts.nft_vault,
+                &ctx.accounts.signer,
+                &ctx.accounts.authority,
+                &ctx.accounts.nft_token_program,
+                &ctx.accounts.associated_token_program.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent.to_account_info(),
+            )?,
+        }
+    }
+
+    *ctx.accounts.participant = ParticipantState {
+        bump: *ctx.bumps.get("participant").unwrap(),
+        battleground: ctx.accounts.battleground.key(),
+        nft_mint,
+        attack,
+        defense,
+        action_points_spent: 0,
+        health_points: combat_config.max_health_points(defense)?,
+        alive: true,
+        placement: 0,
+        prize: 0,
+        contribution,
+        escrowed_dev_fee,
+        escrowed_creator_fee,
+        round: ctx.accounts.battleground.round,
+        team,
+        revives: 0,
+        action_nonce: 0,
+        shield: 0,
+        shield_until: 0,
+        poison_damage: 0,
+        poisoned_until: 0,
+        poison_resolved_at: 0,
+        taunted_by: None,
+        taunted_until: 0,
+        last_action_time: 0,
+        storm_exposure: 0,
+        storm_resolved_at: 0,
+        owner: ctx.accounts.signer.key(),
+        nft_custody,
+        forfeits_owed: 0,
+        ranked: false,
+        next_ranked: None,
+        tied: 0,
+    };
+    let battleground = &mut ctx.accounts.battleground;
+    battleground.participants = battleground.participants.safe_add(1)?;
+    battleground.team_survivors[team as usize] =
+        battleground.team_survivors[team as usize].safe_add(1)?;
 
     emit!(JoinBattlegroundEvent {
         battleground: ctx.accounts.battleground.key(),
-        nft_mint: ctx.accounts.nft_mint.key(),
+        round: ctx.accounts.battleground.round,
+        nft_mint,
+        team,
+        pot_kind: ctx.accounts.battleground.pot_kind.clone(),
         attack,
         defense,
     });
@@ -85,14 +274,13 @@ pub fn join_battleground(
 #[instruction(
     _attack: u32,
     _defense: u32,
-    collection_whitelist_proof: Option<Vec<[u8; 32]>>,
-    holder_whitelist_proof: Option<Vec<[u8; 32]>>
+    _collection_whitelist_proof: Option<Vec<[u8; 32]>>,
+    _holder_whitelist_proof: Option<Vec<[u8; 32]>>,
+    _stats_proof: Option<Vec<[u8; 32]>>,
+    team: u8
 )]
 pub struct JoinBattleground<'info> {
-    #[account(
-        mut,
-        constraint = holder_whitelist_proof.is_none() || verify_holder(holder_whitelist_proof.unwrap(), battleground.whitelist_root.unwrap(), signer.key().to_bytes())
-    )]
+    #[account(mut)]
     pub signer: Signer<'info>,
 
     /// CHECK: Checking correspondance with battle royale state
@@ -111,6 +299,7 @@ pub struct JoinBattleground<'info> {
 
     /// CHECK: Checking correspondance with battle royale state
     #[account(
+        mut,
         seeds = [
             BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
             battleground.id.to_le_bytes().as_ref(),
@@ -136,14 +325,15 @@ pub struct JoinBattleground<'info> {
         has_one = creator,
         constraint = battleground.participants < battleground.participants_cap,
         constraint = battleground.status == BattlegroundStatus::Preparing @ BattleRoyaleError::WrongBattlegroundStatus,
+        constraint = battleground.is_valid_team(team) @ BattleRoyaleError::InvalidTeam,
     )]
     pub battleground: Box<Account<'info, BattlegroundState>>,
 
     /// The participant state
-    /// The participant account must not be alive or must be the winner of the last battle
+    /// An existing participant account must be from a previous round
     /// This prevents reentering with the same NFT
     #[account(
-        init,
+        init_if_needed,
         payer = signer,
         space = ParticipantState::LEN,
         seeds = [
@@ -152,69 +342,75 @@ pub struct JoinBattleground<'info> {
             nft_mint.key().as_ref(),
         ],
         bump,
+        constraint = participant.battleground == Pubkey::default() || participant.round < battleground.round @ BattleRoyaleError::AlreadyParticipating,
+        constraint = participant.prize == 0 @ BattleRoyaleError::UnclaimedPrize,
+        constraint = !battleground.is_forfeited(&participant) @ BattleRoyaleError::NftForfeited,
+        constraint = participant.forfeits_owed == 0 @ BattleRoyaleError::UnclaimedForfeits,
     )]
     pub participant: Account<'info, ParticipantState>,
 
     /// The pot token mint
-    #[account(owner = token::ID)]
-    pub pot_mint: Account<'info, Mint>,
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
 
     /// The NFT used to participate
-    #[account(owner = token::ID)]
-    pub nft_mint: Account<'info, Mint>,
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&nft_mint) @ BattleRoyaleError::InvalidMint)]
+    pub nft_mint: UncheckedAccount<'info>,
 
     /// The token metadata used to verify that the token is part of the collection
     /// CHECK: Safe because there are already enough constraints
     #[account(
         address = mpl_token_metadata::pda::find_metadata_account(&nft_mint.key()).0,
         constraint = mpl_token_metadata::check_id(nft_metadata.owner),
-        constraint = verify_collection(&nft_metadata, &battleground.collection_info, collection_whitelist_proof) @ BattleRoyaleError::CollectionVerificationFailed
+        constraint = verify_collection(&nft_metadata, battleground.team_collection(team))? @ BattleRoyaleError::CollectionVerificationFailed
     )]
     pub nft_metadata: UncheckedAccount<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = pot_mint,
-        associated_token::authority = authority,
-    )]
-    pub pot_account: Box<Account<'info, TokenAccount>>,
+    /// The pot, either a token account of the authority or the authority itself
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub pot_account: UncheckedAccount<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = pot_mint,
-        associated_token::authority = dev_fund,
-    )]
-    pub dev_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub dev_account: UncheckedAccount<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = pot_mint,
-        associated_token::authority = creator,
-    )]
-    pub creator_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub creator_account: UncheckedAccount<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = pot_mint,
-        associated_token::authority = signer,
-    )]
-    pub player_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub player_account: UncheckedAccount<'info>,
 
+    /// CHECK: Token account of either token program holding the NFT
     #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = nft_mint,
-        associated_token::authority = signer,
-        constraint = player_nft_token_account.amount == 1,
+        mut,
+        constraint = participant.can_be_joined_by(&signer.key(), &nft_mint.key(), &player_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
     )]
-    pub player_nft_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_nft_token_account: UncheckedAccount<'info>,
+
+    /// The master edition, freeze authority of soft-staked NFTs
+    /// CHECK: Verified by the token metadata program
+    pub nft_edition: UncheckedAccount<'info>,
+
+    /// The authority's token account holding escrowed NFTs
+    /// CHECK: Verified against the authority's associated token account
+    #[account(mut)]
+    pub nft_vault: UncheckedAccount<'info>,
 
     // Solana ecosystem program addresses
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Either token program, the one owning the pot mint
+    #[account(constraint = token_program.key() == *pot_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: Either token program, the one owning the NFT mint
+    #[account(constraint = nft_token_program.key() == *nft_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub nft_token_program: UncheckedAccount<'info>,
+    /// CHECK: The token metadata program
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
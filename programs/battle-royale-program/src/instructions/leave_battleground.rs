@@ -1,16 +1,122 @@
+use crate::common::*;
 use crate::constants::*;
+use crate::custody::*;
 use crate::errors::*;
 use crate::events::*;
+use crate::pot::*;
 use crate::state::*;
+use crate::token_interface::*;
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::*;
-use anchor_spl::token;
-use anchor_spl::token::*;
 
 pub fn leave_battleground(ctx: Context<LeaveBattleground>) -> Result<()> {
+    // Participants eliminated or from a previous round only reclaim their account rent
+    // Participants still in the lobby withdraw their net entry fee, and the fees escrowed with it
+    let refund = if ctx.accounts.participant.alive
+        && ctx.accounts.participant.round == ctx.accounts.battleground.round
+    {
+        let participant = &ctx.accounts.participant;
+        let team = participant.team as usize;
+        let battleground = &mut ctx.accounts.battleground;
+        battleground.participants = battleground.participants.safe_sub(1)?;
+        battleground.team_survivors[team] = battleground.team_survivors[team].safe_sub(1)?;
+        battleground.escrowed_dev_fees = battleground
+            .escrowed_dev_fees
+            .safe_sub(participant.escrowed_dev_fee)?;
+        battleground.escrowed_creator_fees = battleground
+            .escrowed_creator_fees
+            .safe_sub(participant.escrowed_creator_fee)?;
+        participant
+            .contribution
+            .safe_add(participant.escrowed_dev_fee)?
+            .safe_add(participant.escrowed_creator_fee)?
+    } else {
+        0
+    };
+
+    // Get authority signer seeds
+    let authority_bump = *ctx.bumps.get("authority").unwrap();
+    let authority_seeds = &[
+        BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+        &ctx.accounts.battleground.id.to_le_bytes(),
+        &[authority_bump],
+    ];
+    let authority_signer_seeds = &[&authority_seeds[..]];
+
+    if refund > 0 {
+        let battleground = &ctx.accounts.battleground;
+        let signer = ctx.accounts.signer.to_account_info();
+        verify_pot_account(
+            battleground,
+            &ctx.accounts.pot_mint,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.pot_account,
+        )?;
+        verify_pot_account(
+            battleground,
+            &ctx.accounts.pot_mint,
+            &signer.key(),
+            &ctx.accounts.player_account,
+        )?;
+
+        create_pot_account_if_needed(
+            battleground,
+            &signer,
+            &ctx.accounts.player_account,
+            &signer,
+            &ctx.accounts.pot_mint.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+        )?;
+        transfer_pot_currency(
+            battleground,
+            &ctx.accounts.pot_mint,
+            &ctx.accounts.pot_account,
+            &ctx.accounts.player_account,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            authority_signer_seeds,
+            refund,
+        )?;
+    }
+
+    // Release the NFT from custody
+    match ctx.accounts.participant.nft_custody {
+        NftCustody::Wallet => (),
+        NftCustody::SoftStake => unstake_nft(
+            &ctx.accounts.player_nft_token_account,
+            &ctx.accounts.nft_mint,
+            &ctx.accounts.nft_edition,
+            &ctx.accounts.signer,
+            &ctx.accounts.authority,
+            &ctx.accounts.nft_token_program,
+            &ctx.accounts.token_metadata_program,
+            authority_signer_seeds,
+        )?,
+        NftCustody::Escrow => return_nft(
+            &ctx.accounts.nft_vault,
+            &ctx.accounts.nft_mint,
+            &ctx.accounts.player_nft_token_account,
+            &ctx.accounts.signer,
+            &ctx.accounts.signer,
+            &ctx.accounts.signer,
+            &ctx.accounts.authority,
+            &ctx.accounts.nft_token_program,
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            authority_signer_seeds,
+        )?,
+    }
+
     emit!(LeaveBattlegroundEvent {
         battleground: ctx.accounts.battleground.key(),
         nft_mint: ctx.accounts.nft_mint.key(),
+        pot_kind: ctx.accounts.battleground.pot_kind.clone(),
+        refund,
     });
 
     Ok(())
@@ -30,41 +136,92 @@ pub struct LeaveBattleground<'info> {
     )]
     pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
 
-    /// The battleground the participant is entering
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// The battleground the participant is leaving
     #[account(
+        mut,
         seeds = [
             BATTLEGROUND_STATE_SEEDS.as_bytes(),
             battleground.id.to_le_bytes().as_ref(),
         ],
         bump,
-        constraint = battleground.status == BattlegroundStatus::Preparing @ BattleRoyaleError::WrongBattlegroundStatus,
+        has_one = pot_mint,
     )]
     pub battleground: Box<Account<'info, BattlegroundState>>,
 
     /// The participant state
+    /// Accounts from a previous round are stale and can be closed at any time
+    /// Eliminated participants can leave without waiting for the end of the round
     #[account(
         mut,
         close = signer,
+        seeds = [
+            PARTICIPANT_STATE_SEEDS.as_bytes(),
+            battleground.key().as_ref(),
+            nft_mint.key().as_ref(),
+        ],
+        bump,
         has_one = nft_mint,
-        constraint = !participant.alive
+        constraint = participant.prize == 0 @ BattleRoyaleError::UnclaimedPrize,
+        constraint = !battleground.is_forfeited(&participant) @ BattleRoyaleError::NftForfeited,
+        constraint = participant.forfeits_owed == 0 @ BattleRoyaleError::UnclaimedForfeits,
+        constraint = participant.round < battleground.round || !participant.alive || battleground.status == BattlegroundStatus::Preparing @ BattleRoyaleError::WrongBattlegroundStatus,
     )]
     pub participant: Account<'info, ParticipantState>,
 
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
+
     /// The NFT used to participate
-    #[account(owner = token::ID)]
-    pub nft_mint: Account<'info, Mint>,
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&nft_mint) @ BattleRoyaleError::InvalidMint)]
+    pub nft_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub pot_account: UncheckedAccount<'info>,
 
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub player_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token account of either token program holding the NFT
     #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = nft_mint,
-        associated_token::authority = signer,
-        constraint = player_nft_token_account.amount == 1,
+        mut,
+        constraint = participant.is_controlled_by(&signer.key(), &player_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
     )]
-    pub player_nft_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_nft_token_account: UncheckedAccount<'info>,
+
+    /// The master edition, freeze authority of soft-staked NFTs
+    /// CHECK: Verified by the token metadata program
+    pub nft_edition: UncheckedAccount<'info>,
+
+    /// The authority's token account holding escrowed NFTs
+    /// CHECK: Verified against the authority's associated token account
+    #[account(mut)]
+    pub nft_vault: UncheckedAccount<'info>,
 
     // Solana ecosystem program addresses
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Either token program, the one owning the pot mint
+    #[account(constraint = token_program.key() == *pot_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: Either token program, the one owning the NFT mint
+    #[account(constraint = nft_token_program.key() == *nft_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub nft_token_program: UncheckedAccount<'info>,
+    /// CHECK: The token metadata program
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
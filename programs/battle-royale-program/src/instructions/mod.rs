@@ -1,15 +1,39 @@
+pub mod apply_storm;
+pub mod cancel_battleground;
+pub mod claim_forfeited_nfts;
+pub mod claim_prize;
+pub mod claim_refund;
+pub mod commit_seed;
 pub mod create_battleground;
 pub mod finish_battle;
+pub mod finish_battle_by_timeout;
+pub mod finish_team_battle;
 pub mod initialize;
 pub mod join_battleground;
 pub mod leave_battleground;
 pub mod participant_action;
+pub mod rank_survivor;
+pub mod reveal_seed;
+pub mod revive;
+pub mod settle_survivor;
 pub mod start_battle;
 
+pub use apply_storm::*;
+pub use cancel_battleground::*;
+pub use claim_forfeited_nfts::*;
+pub use claim_prize::*;
+pub use claim_refund::*;
+pub use commit_seed::*;
 pub use create_battleground::*;
 pub use finish_battle::*;
+pub use finish_battle_by_timeout::*;
+pub use finish_team_battle::*;
 pub use initialize::*;
 pub use join_battleground::*;
 pub use leave_battleground::*;
 pub use participant_action::*;
+pub use rank_survivor::*;
+pub use reveal_seed::*;
+pub use revive::*;
+pub use settle_survivor::*;
 pub use start_battle::*;
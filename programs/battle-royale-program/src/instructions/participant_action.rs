@@ -1,85 +1,334 @@
-use crate::common::action_points_available;
+use crate::common::*;
 use crate::constants::*;
 use crate::errors::*;
-use crate::events::ParticipantActionEvent;
+use crate::events::*;
 use crate::state::*;
 use anchor_lang::prelude::*;
-use anchor_spl::token::*;
 
-pub fn participant_action(
-    ctx: Context<ParticipantAction>,
+// Remaining accounts layout:
+// - the state of the scout taunting the participant, to prove it is out of the battle when
+//   harming another target
+pub fn participant_action<'info>(
+    ctx: Context<'_, '_, '_, 'info, ParticipantAction<'info>>,
     action_type: ActionType,
     action_points: u32,
 ) -> Result<()> {
     let participant = &mut ctx.accounts.participant;
     let target = &mut ctx.accounts.target_participant;
+    let combat_config = ctx.accounts.battleground_state.combat_config.clone();
+    let now = ctx.accounts.clock.unix_timestamp;
 
+    // Every action spends points, an empty one would still consume a roll of the seed
     require!(
-        action_points_available(
-            ctx.accounts.battleground_state.start_time,
-            ctx.accounts.clock.unix_timestamp,
-            ctx.accounts.battleground_state.action_points_per_day,
-        ) - participant.action_points_spent
-            >= action_points,
+        action_points > 0,
         BattleRoyaleError::InsufficientActionPoints
     );
 
+    // Unspent points above the bank cap are lost
+    let action_points_earned = action_points_available(
+        ctx.accounts.battleground_state.start_time,
+        now,
+        ctx.accounts.battleground_state.action_points_per_day,
+    )?;
+    let mut action_points_bank = action_points_earned.safe_sub(participant.action_points_spent)?;
+    if let Some(max_bank) = ctx
+        .accounts
+        .battleground_state
+        .action_config
+        .max_action_points_bank
+    {
+        action_points_bank = action_points_bank.min(max_bank as u64);
+    }
+    require!(
+        action_points_bank >= action_points as u64,
+        BattleRoyaleError::InsufficientActionPoints
+    );
+
+    if let Some(cooldown) = ctx.accounts.battleground_state.action_config.cooldown {
+        require!(
+            now.safe_sub(participant.last_action_time)? >= cooldown,
+            BattleRoyaleError::ActionOnCooldown
+        );
+    }
+
+    if let Some(deadline) = ctx.accounts.battleground_state.deadline() {
+        require!(now < deadline, BattleRoyaleError::DeadlinePassed);
+    }
+
+    // Only supportive actions can target the participant itself
+    let self_target = participant.key() == target.key();
+    match action_type {
+        ActionType::Attack | ActionType::Poison | ActionType::Scout => {
+            require!(!self_target, BattleRoyaleError::InvalidTarget)
+        }
+        ActionType::Heal | ActionType::Shield => {
+            let allowed = match ctx.accounts.battleground_state.team_config.heal_policy {
+                HealPolicy::Any => true,
+                HealPolicy::SelfOnly => self_target,
+                HealPolicy::TeamOnly => participant.team == target.team,
+            };
+            require!(allowed, BattleRoyaleError::HealNotAllowed);
+        }
+    }
+
+    // Teammates can only be harmed if the battleground allows friendly fire
+    let battleground = &ctx.accounts.battleground_state;
+    if matches!(
+        action_type,
+        ActionType::Attack | ActionType::Poison | ActionType::Scout
+    ) && battleground.is_team_battle()
+        && participant.team == target.team
+    {
+        require!(
+            battleground.team_config.friendly_fire,
+            BattleRoyaleError::FriendlyFire
+        );
+    }
+
+    // Pending poison and storm damage may finish either participant before the action lands
+    // A self action only resolves the target copy, which is written back last
+    let battleground = &mut ctx.accounts.battleground_state;
+    if !self_target {
+        battleground.resolve_hazards(participant, now)?;
+    }
+    battleground.resolve_hazards(target, now)?;
+    target.expire_shield(now);
+    let health_points_before = target.health_points;
+    if !participant.alive || !target.alive {
+        emit!(ParticipantActionEvent {
+            battleground: ctx.accounts.battleground_state.key(),
+            participant: participant.key(),
+            nft_mint: participant.nft_mint,
+            target: target.key(),
+            target_nft_mint: target.nft_mint,
+            action_type,
+            action_points_spent: 0,
+            nonce: participant.action_nonce,
+            roll: None,
+            critical: false,
+            dodged: false,
+            health_points_before,
+            health_points_after: health_points_before,
+            amount: 0,
+            killed: false,
+        });
+        return Ok(());
+    }
+
+    // Critical hits and dodges of attacks are rolled from the revealed seed of the round
+    // A seed not revealed in time turns them off, so that the battle can't be stalled
+    // Only attacks consume a roll, other actions can't be used to skip a known bad one
+    let nonce = participant.action_nonce;
+    let battleground = &ctx.accounts.battleground_state;
+    let rolled = combat_config.uses_randomness() && action_type == ActionType::Attack;
+    let roll = match battleground.seed {
+        Some(seed) if rolled => Some(action_roll(&seed, &participant.key(), nonce)),
+        None if rolled => {
+            require!(
+                now >= battleground.seed_reveal_deadline(),
+                BattleRoyaleError::RandomnessNotRevealed
+            );
+            None
+        }
+        _ => None,
+    };
+    if roll.is_some() {
+        participant.action_nonce = participant.action_nonce.safe_add(1)?;
+    }
+    let mut critical = false;
+    let mut dodged = false;
+    let mut killed = false;
+
     let spent_points: u32;
+    let amount: u32;
+
+    // The next harmful action of a taunted participant must target the scout,
+    // unless the scout is out of the battle
+    if matches!(action_type, ActionType::Attack | ActionType::Poison) {
+        if let Some(scout) = participant.forced_target(now) {
+            require!(
+                target.key() == scout
+                    || scout_left_battle(
+                        ctx.remaining_accounts.first(),
+                        &scout,
+                        participant.round,
+                        ctx.program_id,
+                    )?,
+                BattleRoyaleError::MustAttackScout
+            );
+        }
+        participant.taunted_by = None;
+    }
 
     match action_type {
         ActionType::Attack => {
-            let health_left = target.health_points;
-            let mut points_needed = health_left / participant.attack;
-            if points_needed * participant.attack < health_left {
-                points_needed += 1;
+            if let Some(roll) = roll {
+                critical = roll_succeeds(&roll, 0, combat_config.crit_chance);
+                dodged = roll_succeeds(&roll, 8, combat_config.dodge_chance);
             }
-            spent_points = if points_needed > action_points {
-                action_points
+
+            // Only spend the points needed to finish the target, through its shield
+            let health_left = target.health_points.safe_add(target.shield)?;
+            let damage_per_point =
+                combat_config.damage_per_point(participant.attack, target.defense, critical)?;
+            spent_points = action_points_needed(health_left, damage_per_point)?.min(action_points);
+            let damage = if dodged {
+                0
             } else {
-                points_needed
+                combat_config.damage(participant.attack, target.defense, critical, spent_points)?
             };
-            let damage = participant.attack * spent_points;
+            let absorbed = damage.min(target.shield);
+            target.shield -= absorbed;
+            let damage = damage - absorbed;
 
+            amount = damage.min(target.health_points);
             if damage >= target.health_points {
-                target.alive = false;
-                target.health_points = 0;
-                ctx.accounts.battleground_state.participants -= 1;
+                killed = true;
+                ctx.accounts.battleground_state.eliminate(target)?;
             } else {
                 target.health_points -= damage;
             }
         }
         ActionType::Heal => {
-            let missing_health = 750 + (target.defense + 50) * 5 - target.health_points;
-            let mut points_needed = missing_health / (participant.defense / 2);
-            if points_needed * participant.defense / 2 < missing_health {
-                points_needed += 1;
-            }
-            spent_points = if points_needed > action_points {
-                action_points
-            } else {
-                points_needed
-            };
-            let heal = spent_points * participant.defense / 2;
-            target.health_points = if target.health_points + heal > 750 + target.defense * 5 {
-                750 + target.defense * 5
-            } else {
-                target.health_points + heal
-            };
+            let max_health_points = combat_config.max_health_points(target.defense)?;
+            require!(
+                target.health_points < max_health_points,
+                BattleRoyaleError::TargetAtFullHealth
+            );
+            let missing_health = max_health_points - target.health_points;
+            let heal_per_point = combat_config.heal(participant.defense, 1)?;
+            spent_points = action_points_needed(missing_health, heal_per_point)?.min(action_points);
+            let heal = combat_config.heal(participant.defense, spent_points)?;
+            amount = heal.min(missing_health);
+            target.health_points += amount;
+        }
+        ActionType::Shield => {
+            // Shields are bought at the heal rate, up to a share of the target's max health points
+            let max_shield = to_u32(
+                combat_config.max_health_points(target.defense)? as u128 * MAX_SHIELD_RATIO as u128
+                    / 10000,
+            )?;
+            require!(
+                target.shield < max_shield,
+                BattleRoyaleError::ShieldAtMaximum
+            );
+            let missing_shield = max_shield - target.shield;
+            let shield_per_point = combat_config.heal(participant.defense, 1)?;
+            spent_points =
+                action_points_needed(missing_shield, shield_per_point)?.min(action_points);
+            let shield = combat_config.heal(participant.defense, spent_points)?;
+            amount = shield.min(missing_shield);
+            target.shield += amount;
+            target.shield_until = now.safe_add(SHIELD_DURATION)?;
+
+            emit!(ShieldEvent {
+                battleground: ctx.accounts.battleground_state.key(),
+                participant: participant.key(),
+                target: target.key(),
+                shield: target.shield,
+                shield_until: target.shield_until,
+            });
+        }
+        ActionType::Poison => {
+            // Poison ignores shields, critical hits and dodges, but takes a whole day to land
+            // Stacking poison spreads what is left over a new duration
+            require!(
+                target.poison_damage < target.health_points,
+                BattleRoyaleError::PoisonAlreadyLethal
+            );
+            let health_left = target.health_points - target.poison_damage;
+            let damage_per_point =
+                combat_config.damage_per_point(participant.attack, target.defense, false)?;
+            spent_points = action_points_needed(health_left, damage_per_point)?.min(action_points);
+            amount = combat_config
+                .damage(participant.attack, target.defense, false, spent_points)?
+                .min(health_left);
+            target.poison_damage += amount;
+            target.poison_resolved_at = now;
+            target.poisoned_until = now.safe_add(POISON_DURATION)?;
+
+            emit!(PoisonEvent {
+                battleground: ctx.accounts.battleground_state.key(),
+                participant: participant.key(),
+                target: target.key(),
+                poison_damage: target.poison_damage,
+                poisoned_until: target.poisoned_until,
+            });
+        }
+        ActionType::Scout => {
+            require!(
+                action_points >= SCOUT_ACTION_POINTS,
+                BattleRoyaleError::InsufficientActionPoints
+            );
+            spent_points = SCOUT_ACTION_POINTS;
+            amount = 0;
+            target.taunted_by = Some(participant.key());
+            target.taunted_until = now.safe_add(SCOUT_DURATION)?;
+
+            emit!(ScoutEvent {
+                battleground: ctx.accounts.battleground_state.key(),
+                participant: participant.key(),
+                target: target.key(),
+                taunted_until: target.taunted_until,
+            });
         }
     };
 
-    participant.action_points_spent += spent_points;
+    // Points lost to the bank cap are counted as spent
+    participant.action_points_spent =
+        action_points_earned.safe_sub(action_points_bank.safe_sub(spent_points as u64)?)?;
+    // Only actions that spent points shelter from the storm and restart the cooldown
+    if spent_points > 0 {
+        participant.last_action_time = now;
+    }
+
+    // Both accounts are written back, the target last, keep the spent points of a self action
+    if self_target {
+        target.action_points_spent = participant.action_points_spent;
+        target.action_nonce = participant.action_nonce;
+        target.last_action_time = participant.last_action_time;
+    }
 
     emit!(ParticipantActionEvent {
         battleground: ctx.accounts.battleground_state.key(),
-        participant: ctx.accounts.participant.key(),
+        participant: participant.key(),
+        nft_mint: participant.nft_mint,
+        target: target.key(),
+        target_nft_mint: target.nft_mint,
         action_type,
-        action_points_spent: action_points
+        action_points_spent: spent_points,
+        nonce,
+        roll,
+        critical,
+        dodged,
+        health_points_before,
+        health_points_after: target.health_points,
+        amount,
+        killed,
     });
 
     Ok(())
 }
 
+// A scout is out of the battle once eliminated, or when its account was closed or reused
+// for another round
+fn scout_left_battle(
+    scout_info: Option<&AccountInfo>,
+    scout: &Pubkey,
+    round: u32,
+    program_id: &Pubkey,
+) -> Result<bool> {
+    let scout_info = match scout_info {
+        Some(scout_info) if scout_info.key() == *scout => scout_info,
+        _ => return Ok(false),
+    };
+    if scout_info.owner != program_id || scout_info.data_is_empty() {
+        return Ok(true);
+    }
+    let scout = Account::<ParticipantState>::try_from(scout_info)?;
+    Ok(!scout.alive || scout.round != round)
+}
+
 #[derive(Accounts)]
 pub struct ParticipantAction<'info> {
     #[account(mut)]
@@ -114,6 +363,7 @@ pub struct ParticipantAction<'info> {
         ],
         bump,
         constraint = participant.alive,
+        constraint = participant.round == battleground_state.round @ BattleRoyaleError::WrongRound,
     )]
     pub participant: Account<'info, ParticipantState>,
 
@@ -125,16 +375,16 @@ pub struct ParticipantAction<'info> {
             target_participant.nft_mint.as_ref(),
         ],
         bump,
-        constraint = participant.alive,
+        constraint = target_participant.alive @ BattleRoyaleError::TargetNotAlive,
+        constraint = target_participant.round == battleground_state.round @ BattleRoyaleError::WrongRound,
     )]
     pub target_participant: Account<'info, ParticipantState>,
 
+    /// CHECK: Token account of either token program holding the NFT
     #[account(
-        constraint = player_nft_token_account.owner == signer.key(),
-        constraint = player_nft_token_account.mint == participant.nft_mint,
-        constraint = player_nft_token_account.amount == 1,
+        constraint = participant.is_controlled_by(&signer.key(), &player_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
     )]
-    pub player_nft_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_nft_token_account: UncheckedAccount<'info>,
 
     pub clock: Sysvar<'info, Clock>,
 }
@@ -0,0 +1,168 @@
+use crate::common::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::RankSurvivorEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+// Survivors are first dealt the poison and storm damage accumulated up to the deadline
+// Free-for-all survivors are then ranked in a list ordered by health points, from the fewest
+// A survivor goes right after the last one with fewer health points, before those it is tied with
+// Team battles rank teams by their survivors once every survivor went through
+// Remaining accounts layout, for free-for-all battles:
+// - the ranked survivor it goes after, if any, whose key is `previous`
+// - the ranked survivor it goes before, if any
+pub fn rank_survivor<'info>(
+    ctx: Context<'_, '_, '_, 'info, RankSurvivor<'info>>,
+    previous: Option<Pubkey>,
+) -> Result<()> {
+    let battleground_key = ctx.accounts.battleground.key();
+    let deadline = ctx
+        .accounts
+        .battleground
+        .deadline()
+        .ok_or(BattleRoyaleError::InvalidRanking)?;
+    ctx.accounts
+        .battleground
+        .resolve_hazards(&mut ctx.accounts.survivor, deadline)?;
+
+    let survivor = &ctx.accounts.survivor;
+    if survivor.alive {
+        if !ctx.accounts.battleground.is_team_battle() {
+            insert_survivor(
+                &mut ctx.accounts.battleground,
+                &mut ctx.accounts.survivor,
+                ctx.remaining_accounts,
+                ctx.program_id,
+                previous,
+            )?;
+        }
+        let survivor = &mut ctx.accounts.survivor;
+        survivor.ranked = true;
+        let battleground = &mut ctx.accounts.battleground;
+        battleground.settlement.ranked = battleground.settlement.ranked.safe_add(1)?;
+    }
+
+    let survivor = &ctx.accounts.survivor;
+    emit!(RankSurvivorEvent {
+        battleground: battleground_key,
+        round: ctx.accounts.battleground.round,
+        nft_mint: survivor.nft_mint,
+        health_points: survivor.health_points,
+    });
+
+    // Once every survivor is ranked, they can be settled
+    let battleground = &mut ctx.accounts.battleground;
+    if battleground.settlement.ranked == battleground.participants {
+        if battleground.participants == 0 {
+            // The hazards left nobody to settle
+            battleground.reset_round()?;
+        } else if battleground.is_team_battle() {
+            let (winning_teams, winners) = battleground.winning_teams();
+            battleground.settlement.winning_teams = winning_teams;
+            battleground.settlement.winners = winners;
+        } else {
+            battleground.settlement.next = battleground.settlement.lowest;
+        }
+    }
+
+    Ok(())
+}
+
+fn insert_survivor<'info>(
+    battleground: &mut Account<'info, BattlegroundState>,
+    survivor: &mut Account<'info, ParticipantState>,
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+    previous: Option<Pubkey>,
+) -> Result<()> {
+    let survivor_key = survivor.key();
+    let health_points = survivor.health_points;
+    let mut remaining_accounts = remaining_accounts.iter();
+
+    let next = match previous {
+        Some(previous_key) => {
+            let previous_info = remaining_accounts
+                .next()
+                .ok_or(BattleRoyaleError::InvalidRanking)?;
+            let mut previous = Account::<ParticipantState>::try_from(previous_info)?;
+            require!(
+                previous.key() == previous_key
+                    && previous.battleground == battleground.key()
+                    && previous.round == battleground.round
+                    && previous.ranked
+                    && previous.health_points < health_points,
+                BattleRoyaleError::InvalidRanking
+            );
+            let next = previous.next_ranked;
+            previous.next_ranked = Some(survivor_key);
+            previous.exit(program_id)?;
+            next
+        }
+        None => {
+            let next = battleground.settlement.lowest;
+            battleground.settlement.lowest = Some(survivor_key);
+            next
+        }
+    };
+
+    // The first of tied survivors counts them
+    survivor.tied = 1;
+    if let Some(next_key) = next {
+        let next_info = remaining_accounts
+            .next()
+            .ok_or(BattleRoyaleError::InvalidRanking)?;
+        let mut next = Account::<ParticipantState>::try_from(next_info)?;
+        require!(
+            next.key() == next_key && next.health_points >= health_points,
+            BattleRoyaleError::InvalidRanking
+        );
+        if next.health_points == health_points {
+            survivor.tied = next.tied.safe_add(1)?;
+            next.tied = 0;
+            next.exit(program_id)?;
+        }
+    }
+    survivor.next_ranked = next;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RankSurvivor<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
+
+    /// The battleground finished by timeout
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.status == BattlegroundStatus::Finished @ BattleRoyaleError::WrongBattlegroundStatus,
+        constraint = battleground.settlement.ranked < battleground.participants @ BattleRoyaleError::InvalidRanking,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_STATE_SEEDS.as_bytes(),
+            battleground.key().as_ref(),
+            survivor.nft_mint.as_ref(),
+        ],
+        bump,
+        constraint = survivor.alive @ BattleRoyaleError::InvalidSurvivors,
+        constraint = !survivor.ranked @ BattleRoyaleError::InvalidRanking,
+        constraint = survivor.round == battleground.round @ BattleRoyaleError::WrongRound,
+    )]
+    pub survivor: Box<Account<'info, ParticipantState>>,
+}
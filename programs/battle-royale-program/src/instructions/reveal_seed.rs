@@ -0,0 +1,66 @@
+use crate::common::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::RevealSeedEvent;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+
+pub fn reveal_seed(ctx: Context<RevealSeed>, secret: [u8; 32]) -> Result<()> {
+    require!(
+        ctx.accounts.battleground.seed_hash == Some(keccak::hash(&secret).0),
+        BattleRoyaleError::InvalidSeed
+    );
+
+    // The most recent slot hash follows the number of entries of the sysvar
+    let mut slot_hash = [0u8; 32];
+    slot_hash.copy_from_slice(&ctx.accounts.recent_slothashes.try_borrow_data()?[16..48]);
+    let seed = mix_seed(&secret, &slot_hash);
+    ctx.accounts.battleground.seed = Some(seed);
+
+    emit!(RevealSeedEvent {
+        battleground: ctx.accounts.battleground.key(),
+        round: ctx.accounts.battleground.round,
+        seed,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevealSeed<'info> {
+    #[account(
+        constraint = signer.key() == battleground.creator || signer.key() == battle_royale.game_master @ BattleRoyaleError::Unauthorized,
+    )]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Account<'info, BattleRoyaleState>,
+
+    /// The battleground of the round, the seed is revealed once the battle started
+    /// and before the reveal period is over
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        constraint = battleground.status == BattlegroundStatus::Ongoing @ BattleRoyaleError::WrongBattlegroundStatus,
+        constraint = battleground.seed.is_none() @ BattleRoyaleError::InvalidSeed,
+        constraint = clock.unix_timestamp < battleground.seed_reveal_deadline() @ BattleRoyaleError::SeedRevealExpired,
+    )]
+    pub battleground: Account<'info, BattlegroundState>,
+
+    /// CHECK: The slot hashes sysvar, too large to be deserialized
+    #[account(address = slot_hashes::ID)]
+    pub recent_slothashes: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
@@ -0,0 +1,169 @@
+use crate::common::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::events::ReviveEvent;
+use crate::pot::*;
+use crate::state::*;
+use crate::token_interface::*;
+use anchor_lang::prelude::*;
+
+pub fn revive(ctx: Context<Revive>) -> Result<()> {
+    let revive_config = ctx
+        .accounts
+        .battleground
+        .revive_config
+        .clone()
+        .ok_or(BattleRoyaleError::RevivesDisabled)?;
+    require!(
+        ctx.accounts.participant.revives < revive_config.max_revives,
+        BattleRoyaleError::MaxRevivesReached
+    );
+    require!(
+        ctx.accounts.battleground.participants >= revive_config.min_participants,
+        BattleRoyaleError::RevivesClosed
+    );
+    if let Some(deadline) = ctx.accounts.battleground.deadline() {
+        require!(
+            ctx.accounts.clock.unix_timestamp < deadline,
+            BattleRoyaleError::DeadlinePassed
+        );
+    }
+
+    // The revive fee goes to the pot, raising the prize of the winners
+    let battleground = &ctx.accounts.battleground;
+    let signer = ctx.accounts.signer.to_account_info();
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.authority.key(),
+        &ctx.accounts.pot_account,
+    )?;
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &signer.key(),
+        &ctx.accounts.player_account,
+    )?;
+    transfer_pot_currency(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.player_account,
+        &ctx.accounts.pot_account,
+        &signer,
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &[],
+        revive_config.fee,
+    )?;
+
+    // Back in the battle with a share of its max health points, and none of its previous effects
+    let max_health_points = ctx
+        .accounts
+        .battleground
+        .combat_config
+        .max_health_points(ctx.accounts.participant.defense)?;
+    if ctx
+        .accounts
+        .battleground
+        .is_forfeited(&ctx.accounts.participant)
+    {
+        let battleground = &mut ctx.accounts.battleground;
+        battleground.forfeited_nfts = battleground.forfeited_nfts.safe_sub(1)?;
+    }
+    let participant = &mut ctx.accounts.participant;
+    participant.alive = true;
+    participant.health_points =
+        to_u32(max_health_points as u128 * revive_config.health_points as u128 / 10000)?.max(1);
+    participant.placement = 0;
+    participant.revives = participant.revives.safe_add(1)?;
+    participant.shield = 0;
+    participant.poison_damage = 0;
+    participant.taunted_by = None;
+    participant.storm_resolved_at = ctx.accounts.clock.unix_timestamp;
+
+    let battleground = &mut ctx.accounts.battleground;
+    let team = participant.team as usize;
+    battleground.participants = battleground.participants.safe_add(1)?;
+    battleground.team_survivors[team] = battleground.team_survivors[team].safe_add(1)?;
+
+    emit!(ReviveEvent {
+        battleground: battleground.key(),
+        round: battleground.round,
+        nft_mint: participant.nft_mint,
+        revives: participant.revives,
+        health_points: participant.health_points,
+        pot_kind: battleground.pot_kind.clone(),
+        fee: revive_config.fee,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Revive<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// The battleground the participant is reentering
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = pot_mint,
+        constraint = battleground.status == BattlegroundStatus::Ongoing @ BattleRoyaleError::WrongBattlegroundStatus,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+
+    /// An eliminated participant of the current round
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_STATE_SEEDS.as_bytes(),
+            battleground.key().as_ref(),
+            participant.nft_mint.as_ref(),
+        ],
+        bump,
+        constraint = !participant.alive @ BattleRoyaleError::ParticipantAlive,
+        constraint = participant.round == battleground.round @ BattleRoyaleError::WrongRound,
+    )]
+    pub participant: Box<Account<'info, ParticipantState>>,
+
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub pot_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub player_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token account of either token program holding the NFT
+    #[account(
+        constraint = participant.is_controlled_by(&signer.key(), &player_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
+    )]
+    pub player_nft_token_account: UncheckedAccount<'info>,
+
+    // Solana ecosystem program addresses
+    /// CHECK: Either token program, the one owning the pot mint
+    #[account(constraint = token_program.key() == *pot_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+}
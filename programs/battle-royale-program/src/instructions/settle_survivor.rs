@@ -0,0 +1,260 @@
+use crate::common::*;
+use crate::constants::*;
+use crate::custody::*;
+use crate::errors::*;
+use crate::events::SettleWinnerEvent;
+use crate::pot::*;
+use crate::state::*;
+use crate::token_interface::*;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::*;
+
+// Free-for-all survivors are settled in ranking order, from the fewest health points
+// Tied survivors share the prizes of the placements they span, the top ones win the rest of the pot
+// Team battle survivors outside the winning teams share the placements below the winners,
+// and the winners are settled last, to share the rest of the pot
+pub fn settle_survivor(ctx: Context<SettleSurvivor>) -> Result<()> {
+    let battleground_key = ctx.accounts.battleground.key();
+    verify_pot_account(
+        &ctx.accounts.battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.authority.key(),
+        &ctx.accounts.pot_account,
+    )?;
+    let pot_amount = pot_balance(&ctx.accounts.battleground, &ctx.accounts.pot_account)?
+        .safe_sub(ctx.accounts.battleground.unclaimed_prizes)?;
+
+    // Get authority signer seeds
+    let authority_bump = *ctx.bumps.get("authority").unwrap();
+    let battleground_id = ctx.accounts.battleground.id.to_le_bytes();
+    let authority_seeds = &[
+        BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+        &battleground_id,
+        &[authority_bump],
+    ];
+    let authority_signer_seeds = &[&authority_seeds[..]];
+
+    let battleground = &mut ctx.accounts.battleground;
+    let survivor = &mut ctx.accounts.survivor;
+    let left = battleground
+        .participants
+        .safe_sub(battleground.settlement.settled)?;
+    if battleground.is_team_battle() {
+        require!(
+            survivor.round == battleground.round && survivor.alive && survivor.placement == 0,
+            BattleRoyaleError::InvalidSurvivors
+        );
+        let participants = battleground.participants;
+        let settlement = &mut battleground.settlement;
+        if !settlement.is_winning_team(survivor.team) {
+            settlement.placement = settlement.winners.safe_add(1)?;
+            settlement.tied = participants.safe_sub(settlement.winners)?;
+        } else {
+            // Only the winners are left once every other survivor is settled
+            require!(
+                left <= settlement.winners,
+                BattleRoyaleError::InvalidSurvivors
+            );
+            if settlement.placement != 1 {
+                settlement.open_winners(pot_amount, settlement.winners);
+            }
+        }
+    } else {
+        require!(
+            battleground.settlement.next == Some(survivor.key()),
+            BattleRoyaleError::InvalidSurvivors
+        );
+
+        // The first of tied survivors opens the placements they share, below the survivors left
+        if survivor.tied > 0 {
+            let settlement = &mut battleground.settlement;
+            settlement.placement = left.safe_sub(survivor.tied)?.safe_add(1)?;
+            settlement.tied = survivor.tied;
+            if settlement.placement == 1 {
+                settlement.open_winners(pot_amount, survivor.tied);
+            }
+        }
+    }
+
+    let placement = battleground.settlement.placement;
+    let tied = battleground.settlement.tied;
+    if placement == 1 {
+        // Winners stay alive and claim their share of the pot like any prize
+        let settlement = &mut battleground.settlement;
+        survivor.placement = placement;
+        survivor.prize = settlement
+            .winners_share
+            .safe_add(settlement.winners_remainder)?;
+        settlement.winners_remainder = 0;
+        battleground.unclaimed_prizes = battleground.unclaimed_prizes.safe_add(survivor.prize)?;
+        // Every survivor left is a winner
+        battleground.owe_forfeits(survivor, left)?;
+
+        emit!(SettleWinnerEvent {
+            battleground: battleground_key,
+            round: battleground.round,
+            nft_mint: survivor.nft_mint,
+            pot_kind: battleground.pot_kind.clone(),
+            pot_mint: battleground.pot_mint,
+            prize: survivor.prize,
+        });
+    } else {
+        let prize = prize_for_placements(
+            battleground.prize_pool,
+            &battleground.payout_table,
+            placement,
+            tied,
+        ) / tied as u64;
+        battleground.record_elimination(survivor, placement, prize)?;
+    }
+
+    // Release the survivor's NFT, it is staked or escrowed again if they join the next round
+    // Forfeited NFTs stay in the vault until they are claimed
+    let forfeited = battleground.is_forfeited(survivor);
+    match survivor.nft_custody {
+        NftCustody::Wallet => (),
+        NftCustody::SoftStake => unstake_nft(
+            &ctx.accounts.owner_nft_token_account,
+            &ctx.accounts.nft_mint,
+            &ctx.accounts.nft_edition,
+            &ctx.accounts.owner,
+            &ctx.accounts.authority,
+            &ctx.accounts.nft_token_program,
+            &ctx.accounts.token_metadata_program,
+            authority_signer_seeds,
+        )?,
+        NftCustody::Escrow if !forfeited => return_nft(
+            &ctx.accounts.nft_vault,
+            &ctx.accounts.nft_mint,
+            &ctx.accounts.owner_nft_token_account,
+            &ctx.accounts.owner,
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.owner,
+            &ctx.accounts.authority,
+            &ctx.accounts.nft_token_program,
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            authority_signer_seeds,
+        )?,
+        NftCustody::Escrow => (),
+    }
+    if !forfeited {
+        survivor.nft_custody = NftCustody::Wallet;
+    }
+
+    let settlement = &mut battleground.settlement;
+    settlement.next = survivor.next_ranked;
+    settlement.settled = settlement.settled.safe_add(1)?;
+
+    // Reset the battleground for the next round once everybody is settled, the winners have to join again
+    if settlement.settled == battleground.participants {
+        battleground.last_winner = if placement == 1 && tied == 1 {
+            Some(survivor.nft_mint)
+        } else {
+            None
+        };
+        battleground.reset_round()?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleSurvivor<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
+        ],
+        bump,
+    )]
+    pub battle_royale: Box<Account<'info, BattleRoyaleState>>,
+
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(
+        seeds = [
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// The finished battleground, with every survivor ranked
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_STATE_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = pot_mint,
+        constraint = battleground.status == BattlegroundStatus::Finished @ BattleRoyaleError::WrongBattlegroundStatus,
+        constraint = battleground.settlement.ranked == battleground.participants @ BattleRoyaleError::RankingIncomplete,
+    )]
+    pub battleground: Box<Account<'info, BattlegroundState>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_STATE_SEEDS.as_bytes(),
+            battleground.key().as_ref(),
+            survivor.nft_mint.as_ref(),
+        ],
+        bump,
+    )]
+    pub survivor: Box<Account<'info, ParticipantState>>,
+
+    /// The player who joined with the survivor's NFT
+    /// CHECK: Checked against the survivor's owner
+    #[account(
+        mut,
+        constraint = owner.key() == survivor.owner @ BattleRoyaleError::InvalidNftTokenAccount,
+    )]
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    pub pot_account: UncheckedAccount<'info>,
+
+    /// CHECK: Owned by either token program
+    #[account(
+        constraint = nft_mint.key() == survivor.nft_mint @ BattleRoyaleError::InvalidMint,
+        constraint = is_mint(&nft_mint) @ BattleRoyaleError::InvalidMint,
+    )]
+    pub nft_mint: UncheckedAccount<'info>,
+
+    /// The owner's token account holding the NFT, unused for NFTs in the wallet custody
+    /// CHECK: Token account of either token program holding the NFT
+    #[account(
+        mut,
+        constraint = survivor.nft_custody == NftCustody::Wallet || survivor.is_controlled_by(&owner.key(), &owner_nft_token_account) @ BattleRoyaleError::InvalidNftTokenAccount,
+    )]
+    pub owner_nft_token_account: UncheckedAccount<'info>,
+
+    /// The master edition, freeze authority of soft-staked NFTs
+    /// CHECK: Verified by the token metadata program
+    pub nft_edition: UncheckedAccount<'info>,
+
+    /// The authority's token account holding escrowed NFTs
+    /// CHECK: Verified against the authority's associated token account
+    #[account(mut)]
+    pub nft_vault: UncheckedAccount<'info>,
+
+    // Solana ecosystem program addresses
+    /// CHECK: Either token program, the one owning the NFT mint
+    #[account(constraint = nft_token_program.key() == *nft_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub nft_token_program: UncheckedAccount<'info>,
+    /// CHECK: The token metadata program
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
@@ -1,15 +1,123 @@
+use crate::common::*;
 use crate::constants::*;
 use crate::errors::*;
 use crate::events::StartBattleEvent;
+use crate::pot::*;
 use crate::state::*;
+use crate::token_interface::*;
 use anchor_lang::prelude::*;
 
 pub fn start_battle(ctx: Context<StartBattle>) -> Result<()> {
+    let battleground = &ctx.accounts.battleground;
+    let signer = ctx.accounts.signer.key();
+    let now = ctx.accounts.clock.unix_timestamp;
+    let enough_participants = battleground.participants >= battleground.min_participants;
+
+    let start_type = if battleground.participants == battleground.participants_cap {
+        StartType::Full
+    } else if enough_participants
+        && battleground.scheduled_start.is_some()
+        && battleground.scheduled_start.unwrap() <= now
+    {
+        StartType::Scheduled
+    } else if enough_participants
+        && (signer == battleground.creator || signer == ctx.accounts.battle_royale.game_master)
+    {
+        StartType::Forced
+    } else {
+        return err!(BattleRoyaleError::CannotStartBattle);
+    };
+
+    // A team battle needs opponents
+    require!(
+        !battleground.is_team_battle()
+            || battleground
+                .team_survivors
+                .iter()
+                .filter(|survivors| **survivors > 0)
+                .count()
+                > 1,
+        BattleRoyaleError::CannotStartBattle
+    );
+
+    // The seed must be committed before anyone can know the participants of the battle
+    require!(
+        !battleground.combat_config.uses_randomness() || battleground.seed_hash.is_some(),
+        BattleRoyaleError::RandomnessNotCommitted
+    );
+
     ctx.accounts.battleground.status = BattlegroundStatus::Ongoing;
-    ctx.accounts.battleground.start_time = ctx.accounts.clock.unix_timestamp;
+    ctx.accounts.battleground.start_time = now;
+
+    // Snapshot the prize pool, excluding prizes still owed from previous battles
+    let escrowed_dev_fees = ctx.accounts.battleground.escrowed_dev_fees;
+    let escrowed_creator_fees = ctx.accounts.battleground.escrowed_creator_fees;
+    let battleground = &ctx.accounts.battleground;
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.authority.key(),
+        &ctx.accounts.pot_account,
+    )?;
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.dev_fund.key(),
+        &ctx.accounts.dev_account,
+    )?;
+    verify_pot_account(
+        battleground,
+        &ctx.accounts.pot_mint,
+        &ctx.accounts.creator.key(),
+        &ctx.accounts.creator_account,
+    )?;
+    ctx.accounts.battleground.prize_pool = pot_balance(battleground, &ctx.accounts.pot_account)?
+        .safe_sub(ctx.accounts.battleground.unclaimed_prizes)?
+        .safe_sub(escrowed_dev_fees)?
+        .safe_sub(escrowed_creator_fees)?;
+
+    // Fees escrowed until the battle starts are not refundable anymore
+    if escrowed_dev_fees > 0 || escrowed_creator_fees > 0 {
+        ctx.accounts.battleground.escrowed_dev_fees = 0;
+        ctx.accounts.battleground.escrowed_creator_fees = 0;
+
+        // Get authority signer seeds
+        let authority_bump = *ctx.bumps.get("authority").unwrap();
+        let authority_seeds = &[
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            &ctx.accounts.battleground.id.to_le_bytes(),
+            &[authority_bump],
+        ];
+        let authority_signer_seeds = &[&authority_seeds[..]];
+
+        transfer_pot_currency(
+            &ctx.accounts.battleground,
+            &ctx.accounts.pot_mint,
+            &ctx.accounts.pot_account,
+            &ctx.accounts.dev_account,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            authority_signer_seeds,
+            escrowed_dev_fees,
+        )?;
+        transfer_pot_currency(
+            &ctx.accounts.battleground,
+            &ctx.accounts.pot_mint,
+            &ctx.accounts.pot_account,
+            &ctx.accounts.creator_account,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            authority_signer_seeds,
+            escrowed_creator_fees,
+        )?;
+    }
 
     emit!(StartBattleEvent {
-        battleground: ctx.accounts.battleground.key()
+        battleground: ctx.accounts.battleground.key(),
+        start_type,
+        participants: ctx.accounts.battleground.participants,
     });
 
     Ok(())
@@ -17,14 +125,37 @@ pub fn start_battle(ctx: Context<StartBattle>) -> Result<()> {
 
 #[derive(Accounts)]
 pub struct StartBattle<'info> {
+    pub signer: Signer<'info>,
+
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(mut)]
+    pub dev_fund: AccountInfo<'info>,
+
     #[account(
         seeds = [
             BATTLE_ROYALE_STATE_SEEDS.as_bytes(),
         ],
         bump,
+        has_one = dev_fund,
     )]
     pub battle_royale: Account<'info, BattleRoyaleState>,
 
+    /// CHECK: Checking correspondance with battle royale state
+    #[account(
+        mut,
+        seeds = [
+            BATTLEGROUND_AUTHORITY_SEEDS.as_bytes(),
+            battleground.id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// Creator of the battleground that owns the creator fee
+    /// CHECK: Matches the battleground's creator
+    #[account(mut)]
+    pub creator: AccountInfo<'info>,
+
     /// The battleground the participant is entering
     #[account(
         mut,
@@ -33,10 +164,33 @@ pub struct StartBattle<'info> {
             battleground.id.to_le_bytes().as_ref(),
         ],
         bump,
+        has_one = pot_mint,
+        has_one = creator,
         constraint = battleground.status == BattlegroundStatus::Preparing @ BattleRoyaleError::WrongBattlegroundStatus,
-        constraint = battleground.participants == battleground.participants_cap,
+        constraint = battleground.winnerless_forfeits == 0 @ BattleRoyaleError::UnclaimedForfeits,
     )]
     pub battleground: Account<'info, BattlegroundState>,
 
+    /// CHECK: Owned by either token program
+    #[account(constraint = is_mint(&pot_mint) @ BattleRoyaleError::InvalidMint)]
+    pub pot_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub pot_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub dev_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified against the battleground pot kind
+    #[account(mut)]
+    pub creator_account: UncheckedAccount<'info>,
+
+    // Solana ecosystem program addresses
+    /// CHECK: Either token program, the one owning the pot mint
+    #[account(constraint = token_program.key() == *pot_mint.owner @ BattleRoyaleError::InvalidTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
     pub clock: Sysvar<'info, Clock>,
 }
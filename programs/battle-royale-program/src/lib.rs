@@ -1,9 +1,12 @@
 mod common;
 mod constants;
+mod custody;
 mod errors;
 mod events;
 mod instructions;
+mod pot;
 mod state;
+mod token_interface;
 
 use crate::common::*;
 use crate::instructions::*;
@@ -34,8 +37,7 @@ pub mod battle_royale_program {
         entry_fee: u64,
         creator: Pubkey,
         creator_fee: u16,
-        action_points_per_day: u32,
-        whitelist_root: Option<[u8; 32]>,
+        params: BattlegroundParams,
     ) -> Result<()> {
         instructions::create_battleground(
             ctx,
@@ -44,8 +46,7 @@ pub mod battle_royale_program {
             entry_fee,
             creator,
             creator_fee,
-            action_points_per_day,
-            whitelist_root,
+            params,
         )
     }
 
@@ -55,6 +56,8 @@ pub mod battle_royale_program {
         defense: u32,
         collection_whitelist_root: Option<Vec<[u8; 32]>>,
         holder_whitelist_root: Option<Vec<[u8; 32]>>,
+        stats_proof: Option<Vec<[u8; 32]>>,
+        team: u8,
     ) -> Result<()> {
         instructions::join_battleground(
             ctx,
@@ -62,6 +65,8 @@ pub mod battle_royale_program {
             defense,
             collection_whitelist_root,
             holder_whitelist_root,
+            stats_proof,
+            team,
         )
     }
 
@@ -69,18 +74,71 @@ pub mod battle_royale_program {
         instructions::start_battle(ctx)
     }
 
-    pub fn participant_action(
-        ctx: Context<ParticipantAction>,
+    pub fn participant_action<'info>(
+        ctx: Context<'_, '_, '_, 'info, ParticipantAction<'info>>,
         action_type: ActionType,
         action_points: u32,
     ) -> Result<()> {
         instructions::participant_action(ctx, action_type, action_points)
     }
 
+    pub fn revive(ctx: Context<Revive>) -> Result<()> {
+        instructions::revive(ctx)
+    }
+
+    pub fn apply_storm(ctx: Context<ApplyStorm>) -> Result<()> {
+        instructions::apply_storm(ctx)
+    }
+
+    pub fn commit_seed(ctx: Context<CommitSeed>, seed_hash: [u8; 32]) -> Result<()> {
+        instructions::commit_seed(ctx, seed_hash)
+    }
+
+    pub fn reveal_seed(ctx: Context<RevealSeed>, secret: [u8; 32]) -> Result<()> {
+        instructions::reveal_seed(ctx, secret)
+    }
+
     pub fn finish_battle(ctx: Context<FinishBattle>) -> Result<()> {
         instructions::finish_battle(ctx)
     }
 
+    pub fn finish_battle_by_timeout(ctx: Context<FinishBattleByTimeout>) -> Result<()> {
+        instructions::finish_battle_by_timeout(ctx)
+    }
+
+    pub fn rank_survivor<'info>(
+        ctx: Context<'_, '_, '_, 'info, RankSurvivor<'info>>,
+        previous: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::rank_survivor(ctx, previous)
+    }
+
+    pub fn settle_survivor(ctx: Context<SettleSurvivor>) -> Result<()> {
+        instructions::settle_survivor(ctx)
+    }
+
+    pub fn finish_team_battle(ctx: Context<FinishTeamBattle>) -> Result<()> {
+        instructions::finish_team_battle(ctx)
+    }
+
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        instructions::claim_prize(ctx)
+    }
+
+    pub fn claim_forfeited_nfts<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimForfeitedNfts<'info>>,
+    ) -> Result<()> {
+        instructions::claim_forfeited_nfts(ctx)
+    }
+
+    pub fn cancel_battleground(ctx: Context<CancelBattleground>) -> Result<()> {
+        instructions::cancel_battleground(ctx)
+    }
+
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        instructions::claim_refund(ctx)
+    }
+
     pub fn leave_battleground(ctx: Context<LeaveBattleground>) -> Result<()> {
         instructions::leave_battleground(ctx)
     }
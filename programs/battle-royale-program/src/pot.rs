@@ -0,0 +1,124 @@
+use crate::errors::*;
+use crate::state::*;
+use crate::token_interface::*;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+
+// Address of the account holding the pot currency for an owner
+// Token pots use associated token accounts, native pots use the owner's system account
+pub fn pot_account_address(
+    battleground: &BattlegroundState,
+    pot_mint: &AccountInfo,
+    owner: &Pubkey,
+) -> Pubkey {
+    match battleground.pot_kind {
+        PotKind::Token => associated_token_address(owner, pot_mint),
+        PotKind::Native => *owner,
+    }
+}
+
+pub fn verify_pot_account(
+    battleground: &BattlegroundState,
+    pot_mint: &AccountInfo,
+    owner: &Pubkey,
+    account: &AccountInfo,
+) -> Result<()> {
+    require!(
+        account.key() == pot_account_address(battleground, pot_mint, owner),
+        BattleRoyaleError::InvalidPotAccount
+    );
+    Ok(())
+}
+
+// Amount held in the pot, the native vault keeps its rent exemption out of it
+pub fn pot_balance(battleground: &BattlegroundState, pot_account: &AccountInfo) -> Result<u64> {
+    match battleground.pot_kind {
+        PotKind::Token => {
+            if pot_account.data_is_empty() {
+                Ok(0)
+            } else {
+                token::accessor::amount(pot_account)
+            }
+        }
+        PotKind::Native => Ok(pot_account
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0))),
+    }
+}
+
+// Share of an amount that made it to the pot when the mint withholds a transfer fee
+pub fn received_share(amount: u64, sent: u64, received: u64) -> u64 {
+    if sent == 0 {
+        return 0;
+    }
+    (amount as u128 * received as u128 / sent as u128) as u64
+}
+
+// Create the associated token account of a token pot if it does not exist yet
+// Native pots pay system accounts directly, so there is nothing to create
+#[allow(clippy::too_many_arguments)]
+pub fn create_pot_account_if_needed<'info>(
+    battleground: &BattlegroundState,
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    pot_mint: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+) -> Result<()> {
+    if battleground.pot_kind == PotKind::Native || !account.data_is_empty() {
+        return Ok(());
+    }
+
+    create_associated_token_account(
+        payer,
+        account,
+        owner,
+        pot_mint,
+        associated_token_program,
+        token_program,
+        system_program,
+        rent,
+    )
+}
+
+// Transfer pot currency out of an account owned by the authority
+// When paying from the native vault, `from` is the authority itself
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_pot_currency<'info>(
+    battleground: &BattlegroundState,
+    pot_mint: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    match battleground.pot_kind {
+        PotKind::Token => transfer_checked(
+            token_program,
+            from,
+            pot_mint,
+            to,
+            authority,
+            signer_seeds,
+            amount,
+        ),
+        PotKind::Native => system_program::transfer(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: from.clone(),
+                    to: to.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        ),
+    }
+}
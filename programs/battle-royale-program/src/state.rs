@@ -1,4 +1,7 @@
 use crate::common::*;
+use crate::constants::*;
+use crate::events::*;
+use crate::token_interface::*;
 use anchor_lang::prelude::*;
 
 #[account]
@@ -20,6 +23,166 @@ pub enum BattlegroundStatus {
     Preparing = 0,
     Ongoing = 1,
     Finished = 2,
+    Cancelled = 3,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum PotKind {
+    // Entry fees are paid in the SPL token `pot_mint`
+    Token = 0,
+    // Entry fees are paid in lamports, `pot_mint` is the native mint
+    Native = 1,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum HealPolicy {
+    // Heals and shields can target any participant
+    Any = 0,
+    // Participants can only heal and shield themselves
+    SelfOnly = 1,
+    // Participants can only heal and shield their teammates, themselves included
+    TeamOnly = 2,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum NftCustody {
+    // NFTs stay in the players' wallets, participants are played by whoever holds them
+    Wallet = 0,
+    // NFTs stay in the players' wallets, frozen while they participate
+    SoftStake = 1,
+    // NFTs are held by the battleground authority while they participate
+    Escrow = 2,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum ForfeitRecipient {
+    // Forfeited NFTs go to the winners of the round
+    Winner = 0,
+    // Forfeited NFTs go to the battleground creator
+    Creator = 1,
+    // Forfeited NFTs are burned
+    Burn = 2,
+}
+
+// How participants are split into teams, and who they can target
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct TeamConfig {
+    pub team_mode: TeamMode,
+    pub friendly_fire: bool,
+    pub heal_policy: HealPolicy,
+}
+
+impl Default for TeamConfig {
+    fn default() -> Self {
+        TeamConfig {
+            team_mode: TeamMode::FreeForAll,
+            friendly_fire: false,
+            heal_policy: HealPolicy::Any,
+        }
+    }
+}
+
+impl TeamConfig {
+    pub const LEN: usize = (TeamMode::LEN) + 1 + 1;
+}
+
+// Custody of the NFTs while they participate
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct CustodyConfig {
+    pub nft_custody: NftCustody,
+    // Escrowed NFTs of eliminated participants are forfeited to it, if set
+    pub forfeit_recipient: Option<ForfeitRecipient>,
+}
+
+impl Default for CustodyConfig {
+    fn default() -> Self {
+        CustodyConfig {
+            nft_custody: NftCustody::Wallet,
+            forfeit_recipient: None,
+        }
+    }
+}
+
+impl CustodyConfig {
+    pub const LEN: usize = 1 + (1 + 1);
+
+    // Only escrowed NFTs can be forfeited
+    pub fn is_valid(&self) -> bool {
+        self.forfeit_recipient.is_none() || self.nft_custody == NftCustody::Escrow
+    }
+}
+
+// Settings of a new battleground, besides its collection, entry fee and creator
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BattlegroundParams {
+    pub action_points_per_day: u32,
+    pub whitelist_root: Option<[u8; 32]>,
+    pub max_duration: Option<i64>,
+    pub payout_table: Vec<u16>,
+    pub min_participants: u32,
+    pub scheduled_start: Option<i64>,
+    pub fees_refundable: bool,
+    pub champion_free_entry: bool,
+    pub pot_kind: PotKind,
+    pub stats_source: StatsSource,
+    pub combat_config: Option<CombatConfig>,
+    pub team_config: Option<TeamConfig>,
+    pub revive_config: Option<ReviveConfig>,
+    pub storm_config: Option<StormConfig>,
+    pub action_config: Option<ActionConfig>,
+    pub custody_config: Option<CustodyConfig>,
+}
+
+// Progress of a finished round, settled one survivor per instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct Settlement {
+    // Survivors ranked so far, and the one with the fewest health points
+    // Team battles rank teams by survivors instead, so survivors are not ranked
+    pub ranked: u32,
+    pub lowest: Option<Pubkey>,
+    // Next survivor to settle, from the fewest health points up to the winners
+    pub next: Option<Pubkey>,
+    pub settled: u32,
+    // Placement shared by the survivors tied with the one being settled, and how many they are
+    pub placement: u32,
+    pub tied: u32,
+    // Share of the pot owed to each winner, the first winner settled also gets the remainder
+    pub winners_share: u64,
+    pub winners_remainder: u64,
+    // Teams the winners belong to as a bit mask, and how many winners they have
+    pub winning_teams: u8,
+    pub winners: u32,
+}
+
+impl Settlement {
+    pub const LEN: usize = 4 + 2 * (1 + 32) + 3 * 4 + 2 * 8 + 1 + 4;
+
+    // Split what is left in the pot between the winners, the first one settled also gets the remainder
+    pub fn open_winners(&mut self, pot_amount: u64, winners: u32) {
+        self.placement = 1;
+        self.tied = winners;
+        self.winners_share = pot_amount / winners as u64;
+        self.winners_remainder = pot_amount - self.winners_share * winners as u64;
+    }
+
+    pub fn is_winning_team(&self, team: u8) -> bool {
+        self.winning_teams & (1 << team) != 0
+    }
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum StartType {
+    // The battleground reached its participants cap
+    Full = 0,
+    // The scheduled start time passed with enough participants
+    Scheduled = 1,
+    // The creator or the game master started the battle early
+    Forced = 2,
 }
 
 #[repr(u8)]
@@ -27,6 +190,12 @@ pub enum BattlegroundStatus {
 pub enum ActionType {
     Attack = 0,
     Heal = 1,
+    // Absorbs the next points of damage dealt to the target, until it wears off
+    Shield = 2,
+    // Damage dealt over time, applied whenever the target is next touched
+    Poison = 3,
+    // Forces the next attack or poison of the target onto the scout
+    Scout = 4,
 }
 
 #[account]
@@ -45,10 +214,229 @@ pub struct BattlegroundState {
     pub creator_fee: u16,
     pub last_winner: Option<Pubkey>,
     pub whitelist_root: Option<[u8; 32]>,
+    pub max_duration: Option<i64>,
+    pub payout_table: Vec<u16>,
+    pub prize_pool: u64,
+    pub unclaimed_prizes: u64,
+    pub min_participants: u32,
+    pub scheduled_start: Option<i64>,
+    pub fees_refundable: bool,
+    pub escrowed_dev_fees: u64,
+    pub escrowed_creator_fees: u64,
+    pub round: u32,
+    pub champion_free_entry: bool,
+    pub pot_kind: PotKind,
+    pub stats_source: StatsSource,
+    pub combat_config: CombatConfig,
+    // Commit-reveal randomness of the current round
+    pub seed_hash: Option<[u8; 32]>,
+    pub seed: Option<[u8; 32]>,
+    pub team_config: TeamConfig,
+    // Participants alive in each team
+    pub team_survivors: Vec<u32>,
+    pub revive_config: Option<ReviveConfig>,
+    pub storm_config: Option<StormConfig>,
+    pub action_config: ActionConfig,
+    pub custody_config: CustodyConfig,
+    // NFTs forfeited in the current round and not yet owed to a winner
+    pub forfeited_nfts: u32,
+    // NFTs forfeited in the last round and left without a winner, they go to the creator instead
+    pub winnerless_forfeits: u32,
+    pub settlement: Settlement,
 }
 
 impl BattlegroundState {
-    pub const LEN: usize = 8 + 1 + 8 + (CollectionInfo::LEN) + 4 + 4 + 2 + 32 + 4 + 32 + 8 + 2 * 33;
+    pub const LEN: usize = 8
+        + 1
+        + 8
+        + (CollectionInfo::LEN)
+        + 4
+        + 4
+        + 2
+        + 32
+        + 4
+        + 32
+        + 8
+        + 2 * 33
+        + (1 + 8)
+        + (4 + 2 * MAX_PAYOUT_RANKS)
+        + 8
+        + 8
+        + 4
+        + (1 + 8)
+        + 1
+        + 8
+        + 8
+        + 4
+        + 1
+        + 1
+        + (StatsSource::LEN)
+        + (CombatConfig::LEN)
+        + 2 * (1 + 32)
+        + (TeamConfig::LEN)
+        + (4 + 4 * MAX_TEAMS)
+        + (1 + ReviveConfig::LEN)
+        + (1 + StormConfig::LEN)
+        + (ActionConfig::LEN)
+        + (CustodyConfig::LEN)
+        + 4
+        + 4
+        + (Settlement::LEN);
+
+    // Timestamp after which the battle can be resolved by timeout, if the battleground has one
+    pub fn deadline(&self) -> Option<i64> {
+        self.max_duration
+            .map(|max_duration| self.start_time.saturating_add(max_duration))
+    }
+
+    // Timestamp until which the seed of the round can be revealed
+    pub fn seed_reveal_deadline(&self) -> i64 {
+        self.start_time.saturating_add(SEED_REVEAL_PERIOD)
+    }
+
+    pub fn is_team_battle(&self) -> bool {
+        self.team_config.team_mode != TeamMode::FreeForAll
+    }
+
+    pub fn is_valid_team(&self, team: u8) -> bool {
+        (team as usize) < self.team_config.team_mode.team_count()
+    }
+
+    // Collection the NFTs of a team must belong to
+    pub fn team_collection(&self, team: u8) -> &CollectionInfo {
+        match &self.team_config.team_mode {
+            TeamMode::ByCollection { collections } => &collections[team as usize],
+            _ => &self.collection_info,
+        }
+    }
+
+    // The single team with survivors, if the others were all eliminated
+    pub fn last_team_standing(&self) -> Option<u8> {
+        let mut teams_alive = self
+            .team_survivors
+            .iter()
+            .enumerate()
+            .filter(|(_, survivors)| **survivors > 0);
+        match (teams_alive.next(), teams_alive.next()) {
+            (Some((team, _)), None) => Some(team as u8),
+            _ => None,
+        }
+    }
+
+    // The battle is decided once a single participant, or a single team, is left standing
+    pub fn is_decided(&self) -> bool {
+        if self.is_team_battle() {
+            self.last_team_standing().is_some()
+        } else {
+            self.participants <= 1
+        }
+    }
+
+    // Teams with the most survivors, as a bit mask, and how many survivors they have together
+    pub fn winning_teams(&self) -> (u8, u32) {
+        let most_survivors = self.team_survivors.iter().copied().max().unwrap_or(0);
+        self.team_survivors
+            .iter()
+            .enumerate()
+            .filter(|(_, survivors)| **survivors == most_survivors)
+            .fold((0, 0), |(teams, winners), (team, survivors)| {
+                (teams | 1 << team, winners + survivors)
+            })
+    }
+
+    // Eliminated participants of a hardcore battleground can't take back their escrowed NFT
+    pub fn is_forfeited(&self, participant: &ParticipantState) -> bool {
+        self.custody_config.forfeit_recipient.is_some()
+            && participant.nft_custody == NftCustody::Escrow
+            && !participant.alive
+    }
+
+    // Forfeited NFTs going to the winners are split between them when they are settled,
+    // rounded up so that the first winners settled get the remainder
+    pub fn owe_forfeits(&mut self, winner: &mut ParticipantState, winners_left: u32) -> Result<()> {
+        if self.custody_config.forfeit_recipient != Some(ForfeitRecipient::Winner) {
+            return Ok(());
+        }
+        let owed = self
+            .forfeited_nfts
+            .safe_add(winners_left.safe_sub(1)?)?
+            .safe_div(winners_left)?;
+        winner.forfeits_owed = owed;
+        self.forfeited_nfts = self.forfeited_nfts.safe_sub(owed)?;
+        Ok(())
+    }
+
+    // Record the elimination order, and the prize owed for that placement
+    pub fn eliminate(&mut self, participant: &mut ParticipantState) -> Result<()> {
+        let prize = prize_for_placements(self.prize_pool, &self.payout_table, self.participants, 1);
+        self.record_elimination(participant, self.participants, prize)?;
+        self.participants = self.participants.safe_sub(1)?;
+        let team = participant.team as usize;
+        self.team_survivors[team] = self.team_survivors[team].safe_sub(1)?;
+        Ok(())
+    }
+
+    // Mark the participant as eliminated at a placement, owing it a prize
+    pub fn record_elimination(
+        &mut self,
+        participant: &mut ParticipantState,
+        placement: u32,
+        prize: u64,
+    ) -> Result<()> {
+        participant.alive = false;
+        participant.health_points = 0;
+        participant.placement = placement;
+        participant.prize = prize;
+        self.unclaimed_prizes = self.unclaimed_prizes.safe_add(prize)?;
+        if self.is_forfeited(participant) {
+            self.forfeited_nfts = self.forfeited_nfts.safe_add(1)?;
+        }
+
+        emit!(ParticipantEliminatedEvent {
+            battleground: participant.battleground,
+            round: participant.round,
+            nft_mint: participant.nft_mint,
+            placement,
+            prize,
+        });
+        Ok(())
+    }
+
+    // Deal the poison and storm damage accumulated since the participant was last touched,
+    // eliminating it if its health points run out
+    // Hazards stop once the battle is decided, so that the last ones standing can finish it
+    pub fn resolve_hazards(&mut self, participant: &mut ParticipantState, now: i64) -> Result<u32> {
+        if self.is_decided() {
+            return Ok(0);
+        }
+        let mut damage = participant.resolve_poison(now);
+        if let Some(storm_config) = &self.storm_config {
+            let storm_start = self.start_time.safe_add(storm_config.grace_period)?;
+            damage =
+                damage.safe_add(participant.resolve_storm(storm_config, storm_start, now)?)?;
+        }
+        if damage > 0 && participant.health_points == 0 {
+            self.eliminate(participant)?;
+        }
+        Ok(damage)
+    }
+
+    // Reset the battleground for the next round, the winners have to join again
+    pub fn reset_round(&mut self) -> Result<()> {
+        self.status = BattlegroundStatus::Preparing;
+        self.participants = 0;
+        self.round = self.round.safe_add(1)?;
+        self.seed_hash = None;
+        self.seed = None;
+        self.team_survivors = vec![0; self.team_config.team_mode.team_count()];
+        // Winners are owed every forfeited NFT when they are settled, any left had no winner
+        if self.custody_config.forfeit_recipient == Some(ForfeitRecipient::Winner) {
+            self.winnerless_forfeits = self.forfeited_nfts;
+        }
+        self.forfeited_nfts = 0;
+        self.settlement = Settlement::default();
+        Ok(())
+    }
 }
 
 #[account]
@@ -59,10 +447,159 @@ pub struct ParticipantState {
     pub attack: u32,
     pub defense: u32,
     pub health_points: u32,
-    pub action_points_spent: u32,
+    pub action_points_spent: u64,
     pub alive: bool,
+    pub placement: u32,
+    pub prize: u64,
+    pub contribution: u64,
+    // Dev and creator fees held in the pot until the battle starts, refunded if it never does
+    pub escrowed_dev_fee: u64,
+    pub escrowed_creator_fee: u64,
+    pub round: u32,
+    pub team: u8,
+    pub revives: u32,
+    pub action_nonce: u64,
+    // Shield points left, until they wear off at `shield_until`
+    pub shield: u32,
+    pub shield_until: i64,
+    // Poison damage left to deal until `poisoned_until`, resolved up to `poison_resolved_at`
+    pub poison_damage: u32,
+    pub poisoned_until: i64,
+    pub poison_resolved_at: i64,
+    // Participant the next attack or poison must target, until `taunted_until`
+    pub taunted_by: Option<Pubkey>,
+    pub taunted_until: i64,
+    pub last_action_time: i64,
+    // Storm exposure in seconds, accumulated up to `storm_resolved_at`
+    pub storm_exposure: u64,
+    pub storm_resolved_at: i64,
+    // Player who joined with the NFT, and the custody the NFT is under until they leave
+    pub owner: Pubkey,
+    pub nft_custody: NftCustody,
+    // Forfeited NFTs of the round a winner can still claim, it can't join again or leave until then
+    pub forfeits_owed: u32,
+    // Ranking of the survivors of a round finished by timeout, see `Settlement`
+    // The first of tied survivors counts them, the others have 0
+    pub ranked: bool,
+    pub next_ranked: Option<Pubkey>,
+    pub tied: u32,
 }
 
 impl ParticipantState {
-    pub const LEN: usize = 8 + 1 + 2 * 32 + 4 * 4 + 1;
+    pub const LEN: usize = 8
+        + 1
+        + 2 * 32
+        + 3 * 4
+        + 8
+        + 1
+        + 4
+        + 8
+        + 8
+        + 8
+        + 8
+        + 4
+        + 1
+        + 4
+        + 8
+        + 4
+        + 8
+        + 4
+        + 8
+        + 8
+        + (1 + 32)
+        + 8
+        + 8
+        + 8
+        + 8
+        + 32
+        + 1
+        + 4
+        + 1
+        + (1 + 32)
+        + 4;
+
+    // Deal the poison damage accumulated since it was last resolved
+    pub fn resolve_poison(&mut self, now: i64) -> u32 {
+        if self.poison_damage == 0 || now <= self.poison_resolved_at {
+            return 0;
+        }
+        let damage = if now >= self.poisoned_until {
+            self.poison_damage
+        } else {
+            (self.poison_damage as i128 * (now - self.poison_resolved_at) as i128
+                / (self.poisoned_until - self.poison_resolved_at) as i128) as u32
+        };
+        self.poison_damage -= damage;
+        self.poison_resolved_at = now;
+        self.health_points = self.health_points.saturating_sub(damage);
+        damage
+    }
+
+    // Deal the storm damage of the time spent idle since it was last resolved
+    // Damage is derived from the total exposure, so that frequent resolutions don't round it away
+    pub fn resolve_storm(
+        &mut self,
+        storm_config: &StormConfig,
+        storm_start: i64,
+        now: i64,
+    ) -> Result<u32> {
+        let from = self.storm_resolved_at.max(storm_start);
+        if now <= from {
+            return Ok(0);
+        }
+        let sheltered_until = self
+            .last_action_time
+            .saturating_add(storm_config.idle_period);
+        let sheltered = (sheltered_until.min(now) - self.last_action_time.max(from)).max(0);
+        let exposure_before = self.storm_exposure;
+        self.storm_exposure = self
+            .storm_exposure
+            .safe_add((now - from - sheltered) as u64)?;
+        self.storm_resolved_at = now;
+
+        let damage_per_day = storm_config.damage_per_day as u128;
+        let damage = (damage_per_day * self.storm_exposure as u128 / 86400)
+            .safe_sub(damage_per_day * exposure_before as u128 / 86400)?
+            .min(self.health_points as u128) as u32;
+        self.health_points -= damage;
+        Ok(damage)
+    }
+
+    // Player controlling the participant: the owner of an escrowed NFT, its holder otherwise
+    pub fn controller(&self, nft_token_account: &AccountInfo) -> Option<Pubkey> {
+        match self.nft_custody {
+            NftCustody::Escrow => Some(self.owner),
+            _ => nft_holder(nft_token_account, &self.nft_mint),
+        }
+    }
+
+    pub fn is_controlled_by(&self, player: &Pubkey, nft_token_account: &AccountInfo) -> bool {
+        self.controller(nft_token_account) == Some(*player)
+    }
+
+    // Player joining with the NFT: the owner of an NFT escrowed in a past round, its holder otherwise
+    // New participant accounts are zeroed, so the NFT mint comes from the instruction
+    pub fn can_be_joined_by(
+        &self,
+        player: &Pubkey,
+        nft_mint: &Pubkey,
+        nft_token_account: &AccountInfo,
+    ) -> bool {
+        match self.nft_custody {
+            NftCustody::Escrow => self.owner == *player,
+            _ => nft_holder(nft_token_account, nft_mint) == Some(*player),
+        }
+    }
+
+    // Shields wear off once their duration is over
+    pub fn expire_shield(&mut self, now: i64) {
+        if now >= self.shield_until {
+            self.shield = 0;
+        }
+    }
+
+    // Participant the next attack or poison is forced onto, if the taunt has not expired
+    pub fn forced_target(&self, now: i64) -> Option<Pubkey> {
+        self.taunted_by.filter(|_| now < self.taunted_until)
+    }
 }
@@ -0,0 +1,216 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::associated_token;
+use anchor_spl::token;
+
+// anchor-spl only knows about the original token program
+pub mod token_2022 {
+    use super::*;
+    declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+// Both programs share the same base layout for mints and token accounts,
+// Token-2022 extensions are appended after the length of a token account and an account type
+const MINT_LEN: usize = 82;
+const MINT_DECIMALS_OFFSET: usize = 44;
+const MINT_INITIALIZED_OFFSET: usize = 45;
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const MINT_ACCOUNT_TYPE: u8 = 1;
+const TRANSFER_CHECKED_INSTRUCTION: u8 = 12;
+const BURN_CHECKED_INSTRUCTION: u8 = 15;
+const CLOSE_ACCOUNT_INSTRUCTION: u8 = 9;
+
+pub fn is_token_program(key: &Pubkey) -> bool {
+    *key == token::ID || *key == token_2022::ID
+}
+
+// Token accounts are longer than mints, so only the exact mint length or a Token-2022 mint
+// with extensions, tagged with its account type, is a mint
+pub fn is_mint(account: &AccountInfo) -> bool {
+    let data = match account.try_borrow_data() {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    let is_mint_layout = if *account.owner == token::ID {
+        data.len() == MINT_LEN
+    } else if *account.owner == token_2022::ID {
+        data.len() == MINT_LEN
+            || (data.len() > TOKEN_ACCOUNT_LEN && data[TOKEN_ACCOUNT_LEN] == MINT_ACCOUNT_TYPE)
+    } else {
+        false
+    };
+    is_mint_layout && data[MINT_INITIALIZED_OFFSET] == 1
+}
+
+pub fn mint_decimals(mint: &AccountInfo) -> Result<u8> {
+    Ok(mint.try_borrow_data()?[MINT_DECIMALS_OFFSET])
+}
+
+// Associated token accounts are derived from the token program owning the mint
+pub fn associated_token_address(owner: &Pubkey, mint: &AccountInfo) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), mint.owner.as_ref(), mint.key.as_ref()],
+        &associated_token::ID,
+    )
+    .0
+}
+
+// Owner of a token account holding the NFT, from either token program
+pub fn nft_holder(account: &AccountInfo, nft_mint: &Pubkey) -> Option<Pubkey> {
+    if !is_token_program(account.owner) || account.data_len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    match (
+        token::accessor::mint(account),
+        token::accessor::amount(account),
+        token::accessor::authority(account),
+    ) {
+        (Ok(mint), Ok(1), Ok(owner)) if mint == *nft_mint => Some(owner),
+        _ => None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_associated_token_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: associated_token::ID,
+        accounts: vec![
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new(account.key(), false),
+            AccountMeta::new_readonly(owner.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(system_program.key(), false),
+            AccountMeta::new_readonly(token_program.key(), false),
+            AccountMeta::new_readonly(rent.key(), false),
+        ],
+        data: vec![],
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            payer.clone(),
+            account.clone(),
+            owner.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            rent.clone(),
+            associated_token_program.clone(),
+        ],
+        &[],
+    )
+    .map_err(Into::into)
+}
+
+// Checked transfer, supported by both token programs and required by Token-2022 fee mints
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    let mut data = vec![TRANSFER_CHECKED_INSTRUCTION];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(mint_decimals(mint)?);
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: vec![
+            AccountMeta::new(from.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new(to.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            from.clone(),
+            mint.clone(),
+            to.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+// Checked burn, supported by both token programs
+pub fn burn_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    let mut data = vec![BURN_CHECKED_INSTRUCTION];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(mint_decimals(mint)?);
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: vec![
+            AccountMeta::new(from.key(), false),
+            AccountMeta::new(mint.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            from.clone(),
+            mint.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+// Close an empty token account, supported by both token programs
+pub fn close_account<'info>(
+    token_program: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: vec![
+            AccountMeta::new(account.key(), false),
+            AccountMeta::new(destination.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+        ],
+        data: vec![CLOSE_ACCOUNT_INSTRUCTION],
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            account.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
@@ -0,0 +1,96 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, BattlegroundStatus, CollectionInfo, Participant } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+import { getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+
+import { expect } from "chai";
+
+describe("Cancel Battleground", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  let provider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMint: anchor.web3.PublicKey;
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participant: Participant;
+  let fee: number;
+  let participantsCap = 2;
+  let initialAmount = 10000;
+  let entryFee = new anchor.BN(100);
+  let creatorFee = 100;
+  let actionPointsPerDay = 10;
+  let collectionInfo: CollectionInfo;
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, player], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, player.publicKey, initialAmount)).mint;
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+    ]);
+    nftMint = mints[0];
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    fee = 100;
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, fee);
+
+    // Create the battleground with refundable fees
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { feesRefundable: true }
+    );
+
+    // Join with one participant
+    participant = await battleground
+      .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+      .join(nftMint, 50, 50);
+  });
+
+  it("can't be cancelled by a player", async () => {
+    await expectRevert(battleground.cancel(), "Unauthorized");
+  });
+
+  it("cancel the battleground", async () => {
+    await battleground
+      .connect(new anchor.AnchorProvider(provider.connection, creator, {}))
+      .cancel();
+    const state = await battleground.getBattlegroundState();
+
+    expect(state.status[BattlegroundStatus.Cancelled]).to.exist;
+  });
+
+  it("refund the entry fee", async () => {
+    await participant.claimRefund();
+
+    const playerAccount = await getAssociatedTokenAddress(potMint, player.publicKey);
+    expect((await getAccount(provider.connection, playerAccount)).amount.toString()).to.equal(
+      initialAmount.toString()
+    );
+    expect((await battleground.getBattlegroundState()).participants).to.equal(0);
+    await expectRevert(participant.getParticipantState(), "Account does not exist");
+  });
+});
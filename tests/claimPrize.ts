@@ -0,0 +1,143 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, CollectionInfo, Participant } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+import {
+  getAccount,
+  getAssociatedTokenAddress,
+  getOrCreateAssociatedTokenAccount,
+  transferChecked,
+} from "@solana/spl-token";
+
+import { expect } from "chai";
+
+describe("Claim Prize", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const players = Array(2)
+    .fill(0)
+    .map((e) => new anchor.Wallet(anchor.web3.Keypair.generate()));
+  let provider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participantsCap = 2;
+  let participants: Participant[] = Array(participantsCap);
+  let fee: number;
+  let initialAmount = 100000;
+  let entryFee = new anchor.BN(1000);
+  let creatorFee = 100;
+  let attack = 50;
+  let defense = 50;
+  let actionPointsPerDay = 8640000;
+  let payoutTable = [7000, 2800];
+  let collectionInfo: CollectionInfo;
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, ...players], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, players[0].publicKey, initialAmount, 8))
+      .mint;
+    // Give half to the other player
+    await transferChecked(
+      provider.connection,
+      players[0].payer,
+      await getAssociatedTokenAddress(potMint, players[0].publicKey),
+      potMint,
+      (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          players[1].payer,
+          potMint,
+          players[1].publicKey
+        )
+      ).address,
+      players[0].publicKey,
+      initialAmount / 2,
+      8
+    );
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(
+      provider,
+      nftSymbol,
+      gameMaster.payer,
+      players.map((e) => e.publicKey)
+    );
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    fee = 100;
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, fee);
+
+    // Create the battleground
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { payoutTable }
+    );
+
+    // Join with all participants
+    for (let i = 0; i < participantsCap; i++) {
+      participants[i] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[i], {}))
+        .join(nftMints[i], attack, defense);
+    }
+
+    // Start the battle
+    await battleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+
+    // Kill the other participant and finish the battle
+    await participants[0].action(participants[1], { attack: {} }, 100);
+    await participants[0].finishBattle();
+  });
+
+  it("pays each placement its share", async () => {
+    const pot =
+      participantsCap *
+      (entryFee.toNumber() - (entryFee.toNumber() * (fee + creatorFee)) / 10000);
+    const secondPrize = (pot * payoutTable[1]) / (payoutTable[0] + payoutTable[1]);
+
+    let state = await participants[1].getParticipantState();
+    expect(state.placement).to.equal(2);
+    expect(state.prize.toNumber()).to.equal(secondPrize);
+
+    const winnerAccount = await getAssociatedTokenAddress(potMint, players[0].publicKey);
+    expect((await getAccount(provider.connection, winnerAccount)).amount.toString()).to.equal(
+      (initialAmount / 2 - entryFee.toNumber() + pot - secondPrize).toString()
+    );
+
+    await participants[1].claimPrize();
+    state = await participants[1].getParticipantState();
+
+    const playerAccount = await getAssociatedTokenAddress(potMint, players[1].publicKey);
+    expect(state.prize.toNumber()).to.equal(0);
+    expect((await getAccount(provider.connection, playerAccount)).amount.toString()).to.equal(
+      (initialAmount / 2 - entryFee.toNumber() + secondPrize).toString()
+    );
+  });
+
+  it("can't claim twice", async () => {
+    await expectRevert(participants[1].claimPrize(), "NoPrizeToClaim");
+  });
+});
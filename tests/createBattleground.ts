@@ -1,8 +1,14 @@
 import * as anchor from "@project-serum/anchor";
 
-import { BattleRoyale, Battleground, BattlegroundStatus, CollectionInfo } from "../ts";
+import {
+  BattleRoyale,
+  Battleground,
+  BattlegroundStatus,
+  CollectionInfo,
+  CombatConfig,
+} from "../ts";
 import { airdropWallets, defaultProvider, gameMaster, smbMints } from "./common";
-import { getMerkleTree, mintCollection } from "./utils";
+import { expectRevert, getMerkleTree, mintCollection } from "./utils";
 
 import { createMint } from "@solana/spl-token";
 import { expect } from "chai";
@@ -129,5 +135,55 @@ describe("Create a Battleground", () => {
       battleRoyaleState = await battleRoyale.getBattleRoyaleState();
       expect(battleRoyaleState.lastBattlegroundId.toNumber()).to.equal(idBefore.toNumber() + 1);
     });
+
+    describe("with custom combat formulas", () => {
+      const collectionInfo = () => ({ v2: { collectionMint } });
+      const combatConfig: CombatConfig = {
+        baseHealthPoints: 1000,
+        healthPointsPerDefense: 10,
+        baseAttack: 80,
+        baseDefense: 40,
+        healDivisor: 4,
+        maxStatistics: 120,
+        defenseMitigation: 100,
+        minDamage: 10,
+        critChance: 0,
+        dodgeChance: 0,
+        critMultiplier: 20000,
+      };
+      const create = (config: CombatConfig) =>
+        battleRoyale.createBattleground(
+          collectionInfo(),
+          potMint,
+          100,
+          new anchor.BN(100),
+          creator.publicKey,
+          creatorFee,
+          10,
+          { combatConfig: config }
+        );
+
+      it("creates a battleground", async () => {
+        const battleground = await create(combatConfig);
+        const state = await battleground.getBattlegroundState();
+
+        expect(JSON.stringify(state.combatConfig)).to.equal(JSON.stringify(combatConfig));
+      });
+
+      it("fails when the formulas overflow", async () => {
+        await expectRevert(
+          create({ ...combatConfig, healthPointsPerDefense: 2 ** 31 }),
+          "InvalidParameter"
+        );
+      });
+
+      it("fails when mitigated attacks deal no damage", async () => {
+        await expectRevert(create({ ...combatConfig, minDamage: 0 }), "InvalidParameter");
+      });
+
+      it("fails when participants can't heal", async () => {
+        await expectRevert(create({ ...combatConfig, healDivisor: 0 }), "InvalidParameter");
+      });
+    });
   });
 });
@@ -142,7 +142,8 @@ describe("Finish Battle", () => {
       );
 
       expect(state.status[BattlegroundStatus.Preparing]).exist;
-      expect(state.participants).to.equal(1);
+      expect(state.participants).to.equal(0);
+      expect(state.round).to.equal(1);
       expect(state.lastWinner?.toString()).to.equal(nftMints[0].toString());
       expect((await getAccount(provider.connection, winnerAccount)).amount.toString()).to.equal(
         (
@@ -151,6 +152,84 @@ describe("Finish Battle", () => {
         ).toString()
       );
     });
+
+    it("winner joins the next round", async () => {
+      await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .join(nftMints[0], 50, 50);
+      const state = await participants[0].getParticipantState();
+
+      expect(state.round).to.equal(1);
+      expect(state.alive).to.be.true;
+      expect((await battleground.getBattlegroundState()).participants).to.equal(1);
+    });
+
+    it("a loser of the previous round joins again with its stale account", async () => {
+      expect((await participants[1].getParticipantState()).round).to.equal(0);
+      await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .join(nftMints[1], 50, 50);
+      const state = await participants[1].getParticipantState();
+
+      expect(state.round).to.equal(1);
+      expect(state.alive).to.be.true;
+      expect(state.placement).to.equal(0);
+      expect(state.healthPoints).to.be.greaterThan(0);
+      expect((await battleground.getBattlegroundState()).participants).to.equal(2);
+    });
+  });
+
+  describe("The defending champion", () => {
+    const playerBalance = async () =>
+      (
+        await getAccount(
+          provider.connection,
+          await getAssociatedTokenAddress(potMint, player.publicKey)
+        )
+      ).amount;
+
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { championFreeEntry: true }
+      );
+      for (let i = 0; i < participantsCap; i++) {
+        participants[i] = await battleground
+          .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+          .join(nftMints[i], 50, 50);
+      }
+      await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+      await participants[0].action(participants[1], { attack: {} }, 100);
+      await participants[0].finishBattle();
+    });
+
+    it("joins the next round for free", async () => {
+      const balanceBefore = await playerBalance();
+      await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .join(nftMints[0], 50, 50);
+
+      expect(await playerBalance()).to.equal(balanceBefore);
+      expect((await participants[0].getParticipantState()).contribution.toNumber()).to.equal(0);
+    });
+
+    it("other players still pay the entry fee", async () => {
+      const balanceBefore = await playerBalance();
+      await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .join(nftMints[1], 50, 50);
+
+      expect(balanceBefore - (await playerBalance())).to.equal(BigInt(entryFee.toNumber()));
+    });
   });
 
   describe("There is no winner", () => {
@@ -0,0 +1,182 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, BattlegroundStatus, CollectionInfo, Participant } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+import {
+  getAccount,
+  getAssociatedTokenAddress,
+  getOrCreateAssociatedTokenAccount,
+  transferChecked,
+} from "@solana/spl-token";
+
+import { expect } from "chai";
+
+describe("Finish Battle by timeout", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const players = Array(2)
+    .fill(0)
+    .map((e) => new anchor.Wallet(anchor.web3.Keypair.generate()));
+  let provider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participantsCap = 2;
+  let participants: Participant[] = Array(participantsCap);
+  let fee: number;
+  let initialAmount = 10000;
+  let entryFee = new anchor.BN(100);
+  let creatorFee = 100;
+  let attack = 50;
+  let defense = 50;
+  let actionPointsPerDay = 8640000;
+  let maxDuration = new anchor.BN(3);
+  let collectionInfo: CollectionInfo;
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, ...players], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, players[0].publicKey, initialAmount, 8))
+      .mint;
+    // Give half to the other player
+    await transferChecked(
+      provider.connection,
+      players[0].payer,
+      await getAssociatedTokenAddress(potMint, players[0].publicKey),
+      potMint,
+      (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          players[1].payer,
+          potMint,
+          players[1].publicKey
+        )
+      ).address,
+      players[0].publicKey,
+      initialAmount / 2,
+      8
+    );
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(
+      provider,
+      nftSymbol,
+      gameMaster.payer,
+      players.map((e) => e.publicKey)
+    );
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    fee = 100;
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, fee);
+
+    // Create the battleground
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { maxDuration }
+    );
+
+    // Join with all participants
+    for (let i = 0; i < participantsCap; i++) {
+      participants[i] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[i], {}))
+        .join(nftMints[i], attack, defense);
+    }
+
+    // Start the battle
+    await battleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+
+    // Wound the other participant without killing it
+    await participants[0].action(participants[1], { attack: {} }, 1);
+  });
+
+  it("can't be finished before the deadline", async () => {
+    await expectRevert(battleground.finishByTimeout(), "DeadlineNotReached");
+  });
+
+  it("finish battle after the deadline", async () => {
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 4000));
+    await battleground.finishByTimeout();
+
+    const state = await battleground.getBattlegroundState();
+    expect(state.status[BattlegroundStatus.Finished]).exist;
+    expect(state.settlement.ranked).to.equal(0);
+  });
+
+  it("can't settle before every survivor is ranked", async () => {
+    await expectRevert(battleground.settleSurvivor(participants[1]), "RankingIncomplete");
+  });
+
+  it("can't rank a survivor out of order", async () => {
+    await battleground.rankSurvivor(participants[1]);
+    // The healthiest survivor can't go before the wounded one
+    await expectRevert(battleground.rankSurvivor(participants[0]), "InvalidRanking");
+    await battleground.rankSurvivor(participants[0], participants[1]);
+
+    const state = await battleground.getBattlegroundState();
+    expect(state.settlement.ranked).to.equal(participantsCap);
+    expect(state.settlement.next?.toString()).to.equal(
+      participants[1].addresses.participant.toString()
+    );
+  });
+
+  it("can't settle the winner before the other survivors", async () => {
+    await expectRevert(battleground.settleSurvivor(participants[0]), "InvalidSurvivors");
+  });
+
+  it("settles the survivors with the healthiest winning", async () => {
+    const program = battleground.program;
+    let eliminatedEvent: any;
+    const listener = program.addEventListener(
+      "ParticipantEliminatedEvent",
+      (e) => (eliminatedEvent = e)
+    );
+    await battleground.settleSurvivors(participants);
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    await program.removeEventListener(listener);
+    expect(eliminatedEvent.nftMint.toBase58()).to.equal(nftMints[1].toBase58());
+    expect(eliminatedEvent.placement).to.equal(2);
+
+    const state = await battleground.getBattlegroundState();
+    expect(state.status[BattlegroundStatus.Preparing]).exist;
+    expect(state.participants).to.equal(0);
+    expect(state.lastWinner?.toString()).to.equal(nftMints[0].toString());
+    expect((await participants[1].getParticipantState()).alive).to.be.false;
+    expect((await participants[1].getParticipantState()).placement).to.equal(2);
+    expect((await participants[0].getParticipantState()).placement).to.equal(1);
+  });
+
+  it("lets the winner claim the pot", async () => {
+    await participants[0].claimPrize();
+
+    const winnerAccount = await getAssociatedTokenAddress(potMint, players[0].publicKey);
+    expect((await getAccount(provider.connection, winnerAccount)).amount.toString()).to.equal(
+      (
+        initialAmount / 2 +
+        entryFee.toNumber() -
+        (participantsCap * (entryFee.toNumber() * (fee + creatorFee))) / 10000
+      ).toString()
+    );
+  });
+});
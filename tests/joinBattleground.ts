@@ -6,6 +6,8 @@ import {
   expectRevert,
   getMerkleProof,
   getMerkleTree,
+  getStatLeaf,
+  getStatTable,
   mintCollection,
   mintNft,
   mintToken,
@@ -153,7 +155,7 @@ describe("Join a Battleground", () => {
         creator.publicKey,
         creatorFee,
         actionPointsPerDay,
-        [...merkleTree.getRoot()]
+        { whitelistRoot: [...merkleTree.getRoot()] }
       );
     });
 
@@ -209,8 +211,97 @@ describe("Join a Battleground", () => {
           null,
           merkleTree.getProof(leaf).map((e) => [...e.data])
         ),
-        "ConstraintRaw"
+        "NotWhitelisted"
+      );
+    });
+
+    it("missing holder proof", async () => {
+      await expectRevert(
+        battleground
+          .connect(new anchor.AnchorProvider(provider.connection, players[1], {}))
+          .join(nftMints[1], 50, 50),
+        "WhitelistProofMissing"
+      );
+    });
+  });
+
+  describe("deriving statistics", () => {
+    const stats = [
+      { attack: 90, defense: 80 },
+      { attack: 20, defense: 10 },
+    ];
+    let statTable: MerkleTree;
+
+    before(async () => {
+      const { root, tree } = getStatTable(stats.map((e, i) => ({ mint: nftMints[i * 2], ...e })));
+      statTable = tree;
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { statsSource: { statTable: { root } } }
+      );
+    });
+
+    it("join with the stat table", async () => {
+      const { attack, defense } = stats[0];
+      const participant = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+        .join(
+          nftMints[0],
+          attack,
+          defense,
+          null,
+          null,
+          statTable.getProof(getStatLeaf(nftMints[0], attack, defense)).map((e) => [...e.data])
+        );
+      const state = await participant.getParticipantState();
+
+      expect(state.attack).to.equal(100 + attack);
+      expect(state.defense).to.equal(50 + defense);
+    });
+
+    it("fails with statistics not in the stat table", async () => {
+      const { attack, defense } = stats[1];
+      await expectRevert(
+        battleground
+          .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+          .join(
+            nftMints[2],
+            attack + 10,
+            defense,
+            null,
+            null,
+            statTable.getProof(getStatLeaf(nftMints[2], attack, defense)).map((e) => [...e.data])
+          ),
+        "StatsVerificationFailed"
       );
     });
+
+    it("join with statistics derived from the mint", async () => {
+      const mintHashBattleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { statsSource: { mintHash: {} } }
+      );
+      // Declared statistics are ignored
+      const participant = await mintHashBattleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+        .join(nftMints[0], 0, 100);
+      const state = await participant.getParticipantState();
+
+      const attack = keccak256(nftMints[0].toBuffer()).readUInt16LE(0) % 101;
+      expect(state.attack).to.equal(100 + attack);
+      expect(state.defense).to.equal(50 + 100 - attack);
+    });
   });
 });
@@ -3,7 +3,10 @@ import * as anchor from "@project-serum/anchor";
 import { BattleRoyale, Battleground, CollectionInfo, Participant } from "../ts";
 import { airdropWallets, gameMaster } from "./common";
 import { expectRevert, mintNft, mintToken, verifyCollection } from "./utils";
+
+import { expect } from "chai";
 import {
+  getAccount,
   getAssociatedTokenAddress,
   getOrCreateAssociatedTokenAccount,
   transferChecked,
@@ -137,4 +140,70 @@ describe("Leave Battleground", () => {
     await participants[1].leave();
     await expectRevert(participants[1].getParticipantState(), "Account does not exist");
   });
+
+  it("reclaims the rent of a stale account", async () => {
+    // The winner did not join the next round, its account is left over from the last one
+    const rent = await provider.connection.getBalance(participants[0].addresses.participant);
+    const balanceBefore = await provider.connection.getBalance(players[0].publicKey);
+    await participants[0].leave();
+
+    // The leaving player also pays the transaction fee
+    expect(await provider.connection.getBalance(players[0].publicKey)).to.equal(
+      balanceBefore + rent - 5000
+    );
+    await expectRevert(participants[0].getParticipantState(), "Account does not exist");
+  });
+
+  it("withdraw from a lobby before the battle starts", async () => {
+    const lobby = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay
+    );
+    const participant = await lobby
+      .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+      .join(nftMints[0], attack, defense);
+
+    const playerAccount = await getAssociatedTokenAddress(potMint, players[0].publicKey);
+    const balanceBefore = (await getAccount(provider.connection, playerAccount)).amount;
+    await participant.leave();
+    const balanceAfter = (await getAccount(provider.connection, playerAccount)).amount;
+
+    expect((await lobby.getBattlegroundState()).participants).to.equal(0);
+    expect((balanceAfter - balanceBefore).toString()).to.equal(
+      (entryFee.toNumber() - (entryFee.toNumber() * (fee + creatorFee)) / 10000).toString()
+    );
+    await expectRevert(participant.getParticipantState(), "Account does not exist");
+  });
+
+  it("withdraw the escrowed fees with the entry fee from a refundable lobby", async () => {
+    const lobby = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { feesRefundable: true }
+    );
+    const participant = await lobby
+      .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+      .join(nftMints[0], attack, defense);
+    expect((await lobby.getBattlegroundState()).escrowedDevFees.toNumber()).to.be.greaterThan(0);
+
+    const playerAccount = await getAssociatedTokenAddress(potMint, players[0].publicKey);
+    const balanceBefore = (await getAccount(provider.connection, playerAccount)).amount;
+    await participant.leave();
+    const balanceAfter = (await getAccount(provider.connection, playerAccount)).amount;
+
+    const state = await lobby.getBattlegroundState();
+    expect(state.escrowedDevFees.toNumber()).to.equal(0);
+    expect(state.escrowedCreatorFees.toNumber()).to.equal(0);
+    expect((balanceAfter - balanceBefore).toString()).to.equal(entryFee.toString());
+  });
 });
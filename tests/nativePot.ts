@@ -0,0 +1,127 @@
+import * as anchor from "@project-serum/anchor";
+
+import {
+  BattleRoyale,
+  Battleground,
+  BattlegroundStatus,
+  CollectionInfo,
+  Participant,
+  PotKind,
+} from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+
+import { NATIVE_MINT } from "@solana/spl-token";
+import { expect } from "chai";
+
+describe("Native Pot", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  let provider: anchor.AnchorProvider;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participantsCap = 2;
+  let participants: Participant[] = Array(participantsCap);
+  let fee: number;
+  let entryFee = new anchor.BN(anchor.web3.LAMPORTS_PER_SOL);
+  let creatorFee = 100;
+  let actionPointsPerDay = 8640000;
+  let collectionInfo: CollectionInfo;
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, player], provider, 100);
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+      player.publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    fee = 100;
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, fee);
+  });
+
+  it("can't use a token mint", async () => {
+    const potMint = (await mintToken(provider, creator.payer, player.publicKey, 1000)).mint;
+
+    await expectRevert(
+      battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { potKind: PotKind.Native }
+      ),
+      "InvalidParameter"
+    );
+  });
+
+  it("create a native battleground", async () => {
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      NATIVE_MINT,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { potKind: PotKind.Native }
+    );
+    const state = await battleground.getBattlegroundState();
+
+    expect(state.potKind[PotKind.Native]).to.exist;
+    expect(state.potMint.toString()).to.equal(NATIVE_MINT.toString());
+  });
+
+  it("join and play with lamports", async () => {
+    const vaultBalance = await provider.connection.getBalance(battleground.addresses.authority);
+
+    for (let i = 0; i < participantsCap; i++) {
+      participants[i] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .join(nftMints[i], 50, 50);
+    }
+
+    const potAmount =
+      participantsCap * entryFee.toNumber() -
+      (participantsCap * entryFee.toNumber() * (fee + creatorFee)) / 10000;
+    expect(await provider.connection.getBalance(battleground.addresses.authority)).to.equal(
+      vaultBalance + potAmount
+    );
+
+    // Start the battle and kill the other participant
+    await battleground
+      .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+      .start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    await participants[0].action(participants[1], { attack: {} }, 100);
+
+    const playerBalance = await provider.connection.getBalance(player.publicKey);
+    await participants[0].finishBattle();
+
+    expect((await battleground.getBattlegroundState()).status[BattlegroundStatus.Preparing]).to
+      .exist;
+    expect(await provider.connection.getBalance(battleground.addresses.authority)).to.equal(
+      vaultBalance
+    );
+    expect(await provider.connection.getBalance(player.publicKey)).to.be.above(playerBalance);
+  });
+});
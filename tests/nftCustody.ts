@@ -0,0 +1,389 @@
+import * as anchor from "@project-serum/anchor";
+
+import {
+  BattleRoyale,
+  Battleground,
+  CollectionInfo,
+  ForfeitRecipient,
+  NftCustody,
+  Participant,
+} from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+import {
+  getAccount,
+  getAssociatedTokenAddress,
+  getOrCreateAssociatedTokenAccount,
+  transferChecked,
+} from "@solana/spl-token";
+
+import { expect } from "chai";
+
+describe("NFT custody", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const players = Array(2)
+    .fill(0)
+    .map((e) => new anchor.Wallet(anchor.web3.Keypair.generate()));
+  let provider: anchor.AnchorProvider;
+  let playerProviders: anchor.AnchorProvider[];
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participants: Participant[] = [];
+  let collectionInfo: CollectionInfo;
+
+  const createBattleground = (
+    nftCustody: NftCustody,
+    forfeitRecipient?: ForfeitRecipient,
+    maxDuration?: anchor.BN
+  ) =>
+    battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      2,
+      new anchor.BN(100),
+      creator.publicKey,
+      100,
+      8640000,
+      { maxDuration, custodyConfig: { nftCustody, forfeitRecipient } }
+    );
+
+  const nftAmount = async (owner: anchor.web3.PublicKey, nftMint: anchor.web3.PublicKey) =>
+    (await getAccount(provider.connection, await getAssociatedTokenAddress(nftMint, owner, true)))
+      .amount;
+
+  // Emptied vaults are closed
+  const vaultClosed = async (nftMint: anchor.web3.PublicKey) =>
+    (await provider.connection.getAccountInfo(
+      await getAssociatedTokenAddress(nftMint, battleground.addresses.authority, true)
+    )) === null;
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+    playerProviders = players.map(
+      (player) => new anchor.AnchorProvider(provider.connection, player, {})
+    );
+
+    await airdropWallets([gameMaster, creator, ...players], provider);
+
+    // Create the pot token and mint some to the players
+    potMint = (await mintToken(provider, creator.payer, players[0].publicKey, 10000)).mint;
+    await transferChecked(
+      provider.connection,
+      players[0].payer,
+      await getAssociatedTokenAddress(potMint, players[0].publicKey),
+      potMint,
+      (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          players[0].payer,
+          potMint,
+          players[1].publicKey
+        )
+      ).address,
+      players[0].publicKey,
+      5000,
+      8
+    );
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      players[0].publicKey,
+      players[0].publicKey,
+      players[1].publicKey,
+      players[1].publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, 100);
+  });
+
+  describe("soft-staking", () => {
+    let nftTokenAccount: anchor.web3.PublicKey;
+
+    before(async () => {
+      battleground = await createBattleground(NftCustody.SoftStake);
+      nftTokenAccount = await getAssociatedTokenAddress(nftMints[0], players[0].publicKey);
+    });
+
+    it("freezes the NFT on join", async () => {
+      participants[0] = await battleground.connect(playerProviders[0]).join(nftMints[0], 50, 50);
+
+      const account = await getAccount(provider.connection, nftTokenAccount);
+      expect(account.isFrozen).to.be.true;
+      expect(account.delegate.toString()).to.equal(battleground.addresses.authority.toString());
+      expect((await participants[0].getParticipantState()).nftCustody).to.deep.equal({
+        softStake: {},
+      });
+    });
+
+    it("can't transfer a staked NFT", async () => {
+      const receiverAccount = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        players[0].payer,
+        nftMints[0],
+        anchor.web3.Keypair.generate().publicKey
+      );
+      await expectRevert(
+        transferChecked(
+          provider.connection,
+          players[0].payer,
+          nftTokenAccount,
+          nftMints[0],
+          receiverAccount.address,
+          players[0].publicKey,
+          1,
+          0
+        )
+      );
+    });
+
+    it("releases the NFT on leave", async () => {
+      await participants[0].leave();
+
+      const account = await getAccount(provider.connection, nftTokenAccount);
+      expect(account.isFrozen).to.be.false;
+      expect(account.delegate).to.be.null;
+    });
+  });
+
+  describe("soft-staking battle", () => {
+    const isFrozen = async (owner: anchor.web3.PublicKey, nftMint: anchor.web3.PublicKey) =>
+      (await getAccount(provider.connection, await getAssociatedTokenAddress(nftMint, owner)))
+        .isFrozen;
+
+    before(async () => {
+      battleground = await createBattleground(NftCustody.SoftStake);
+      participants[0] = await battleground.connect(playerProviders[0]).join(nftMints[0], 50, 50);
+      participants[1] = await battleground.connect(playerProviders[1]).join(nftMints[2], 50, 50);
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+      await participants[0].action(participants[1], { attack: {} }, 50);
+    });
+
+    it("an eliminated participant can leave during the battle", async () => {
+      expect(await isFrozen(players[1].publicKey, nftMints[2])).to.be.true;
+      await participants[1].leave();
+
+      expect(await isFrozen(players[1].publicKey, nftMints[2])).to.be.false;
+    });
+
+    it("releases the NFT of the winner", async () => {
+      expect(await isFrozen(players[0].publicKey, nftMints[0])).to.be.true;
+      await participants[0].finishBattle();
+
+      expect(await isFrozen(players[0].publicKey, nftMints[0])).to.be.false;
+      expect((await participants[0].getParticipantState()).nftCustody).to.deep.equal({
+        wallet: {},
+      });
+    });
+  });
+
+  describe("escrow", () => {
+    before(async () => {
+      battleground = await createBattleground(NftCustody.Escrow);
+    });
+
+    it("escrows the NFT on join", async () => {
+      for (let i = 0; i < players.length; i++) {
+        participants[i] = await battleground
+          .connect(playerProviders[i])
+          .join(nftMints[i + 1], 50, 50);
+      }
+
+      expect(await nftAmount(players[0].publicKey, nftMints[1])).to.equal(BigInt(0));
+      expect(await nftAmount(battleground.addresses.authority, nftMints[1])).to.equal(BigInt(1));
+      const state = await participants[0].getParticipantState();
+      expect(state.owner.toString()).to.equal(players[0].publicKey.toString());
+      expect(state.nftCustody).to.deep.equal({ escrow: {} });
+    });
+
+    it("only the owner can act", async () => {
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+
+      const impostor = new Participant(battleground, nftMints[1], playerProviders[1]);
+      await expectRevert(
+        impostor.action(participants[1], { attack: {} }, 1),
+        "InvalidNftTokenAccount"
+      );
+    });
+
+    it("returns the NFT to the winner", async () => {
+      await participants[0].action(participants[1], { attack: {} }, 50);
+      await participants[0].finishBattle();
+
+      expect(await nftAmount(players[0].publicKey, nftMints[1])).to.equal(BigInt(1));
+      expect(await vaultClosed(nftMints[1])).to.be.true;
+    });
+
+    it("returns the NFT on leave", async () => {
+      await participants[1].leave();
+
+      expect(await nftAmount(players[1].publicKey, nftMints[2])).to.equal(BigInt(1));
+      expect(await vaultClosed(nftMints[2])).to.be.true;
+    });
+  });
+
+  describe("escrow timeout", () => {
+    before(async () => {
+      battleground = await createBattleground(NftCustody.Escrow, undefined, new anchor.BN(3));
+      for (let i = 0; i < players.length; i++) {
+        participants[i] = await battleground
+          .connect(playerProviders[i])
+          .join(nftMints[i + 1], 50, 50);
+      }
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 4000));
+    });
+
+    it("returns the NFTs of every survivor on settlement", async () => {
+      await battleground.finishByTimeout();
+      await battleground.rankSurvivors(participants);
+      await battleground.settleSurvivors(participants);
+
+      expect(await nftAmount(players[0].publicKey, nftMints[1])).to.equal(BigInt(1));
+      expect(await nftAmount(players[1].publicKey, nftMints[2])).to.equal(BigInt(1));
+      expect(await vaultClosed(nftMints[1])).to.be.true;
+      expect(await vaultClosed(nftMints[2])).to.be.true;
+      for (const participant of participants) {
+        expect((await participant.getParticipantState()).nftCustody).to.deep.equal({
+          wallet: {},
+        });
+      }
+    });
+  });
+
+  describe("hardcore", () => {
+    before(async () => {
+      battleground = await createBattleground(NftCustody.Escrow, ForfeitRecipient.Winner);
+      for (let i = 0; i < players.length; i++) {
+        participants[i] = await battleground
+          .connect(playerProviders[i])
+          .join(nftMints[i + 1], 50, 50);
+      }
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+      await participants[0].action(participants[1], { attack: {} }, 50);
+      await participants[0].finishBattle();
+    });
+
+    it("can't take back a forfeited NFT", async () => {
+      await expectRevert(participants[1].leave(), "NftForfeited");
+    });
+
+    it("the winner can't leave before claiming the forfeited NFTs", async () => {
+      expect((await participants[0].getParticipantState()).forfeitsOwed).to.equal(1);
+      await expectRevert(participants[0].leave(), "UnclaimedForfeits");
+    });
+
+    it("forfeits the NFT to the winner", async () => {
+      let forfeitEvent: any;
+      const listener = battleground.program.addEventListener(
+        "ForfeitNftEvent",
+        (e) => (forfeitEvent = e)
+      );
+      await battleground.claimForfeitedNfts([participants[1]], participants[0]);
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+      await battleground.program.removeEventListener(listener);
+
+      expect(await nftAmount(players[0].publicKey, nftMints[2])).to.equal(BigInt(1));
+      expect(await vaultClosed(nftMints[2])).to.be.true;
+      expect(forfeitEvent.nftMint.toString()).to.equal(nftMints[2].toString());
+      expect(forfeitEvent.recipient.toString()).to.equal(players[0].publicKey.toString());
+
+      // The forfeited participant is closed
+      const participantAccount = await provider.connection.getAccountInfo(
+        participants[1].addresses.participant
+      );
+      expect(participantAccount).to.be.null;
+      expect((await participants[0].getParticipantState()).forfeitsOwed).to.equal(0);
+    });
+  });
+
+  describe("hardcore timeout", () => {
+    const maxDuration = 4;
+    let winners: Participant[];
+    let winnerProviders: anchor.AnchorProvider[];
+    let owedIndex: number;
+
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        3,
+        new anchor.BN(100),
+        creator.publicKey,
+        100,
+        8640000,
+        {
+          maxDuration: new anchor.BN(maxDuration),
+          custodyConfig: {
+            nftCustody: NftCustody.Escrow,
+            forfeitRecipient: ForfeitRecipient.Winner,
+          },
+        }
+      );
+      participants = [
+        await battleground.connect(playerProviders[0]).join(nftMints[0], 50, 50),
+        await battleground.connect(playerProviders[0]).join(nftMints[1], 50, 50),
+        await battleground.connect(playerProviders[1]).join(nftMints[3], 50, 50),
+      ];
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+      await participants[2].action(participants[1], { attack: {} }, 50);
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), maxDuration * 1000));
+
+      // The two untouched survivors are tied winners
+      winners = [participants[0], participants[2]];
+      winnerProviders = [playerProviders[0], playerProviders[1]];
+      await battleground.finishByTimeout();
+      await battleground.rankSurvivors(winners);
+      await battleground.settleSurvivors(winners);
+    });
+
+    it("splits the forfeited NFTs between the winners", async () => {
+      const owed = await Promise.all(
+        winners.map(async (e) => (await e.getParticipantState()).forfeitsOwed)
+      );
+      // A single forfeited NFT goes to the first winner settled
+      expect([...owed].sort()).to.deep.equal([0, 1]);
+      owedIndex = owed.indexOf(1);
+    });
+
+    it("a winner owed nothing can't claim", async () => {
+      await expectRevert(
+        battleground.claimForfeitedNfts([participants[1]], winners[1 - owedIndex]),
+        "InvalidForfeitRecipient"
+      );
+    });
+
+    it("the winner can't join again before claiming the forfeited NFTs", async () => {
+      await winners[owedIndex].claimPrize();
+      await expectRevert(
+        battleground.connect(winnerProviders[owedIndex]).join(winners[owedIndex].nft, 50, 50),
+        "UnclaimedForfeits"
+      );
+    });
+
+    it("the owed winner claims the forfeited NFT", async () => {
+      await battleground.claimForfeitedNfts([participants[1]], winners[owedIndex]);
+
+      const recipient = winnerProviders[owedIndex].publicKey;
+      expect(await nftAmount(recipient, nftMints[1])).to.equal(BigInt(1));
+      expect((await winners[owedIndex].getParticipantState()).forfeitsOwed).to.equal(0);
+    });
+  });
+});
@@ -1,13 +1,20 @@
 import * as anchor from "@project-serum/anchor";
 
-import { BattleRoyale, Battleground, CollectionInfo, Participant } from "../ts";
+import {
+  BattleRoyale,
+  Battleground,
+  CollectionInfo,
+  CombatConfig,
+  HealPolicy,
+  Participant,
+} from "../ts";
 import { airdropWallets, gameMaster } from "./common";
 import {
   getAssociatedTokenAddress,
   getOrCreateAssociatedTokenAccount,
   transferChecked,
 } from "@solana/spl-token";
-import { mintNft, mintToken, verifyCollection } from "./utils";
+import { expectRevert, mintNft, mintToken, verifyCollection } from "./utils";
 
 import { expect } from "chai";
 
@@ -86,11 +93,19 @@ describe("Participant action", () => {
       players[1].publicKey,
       collectionMint
     );
-    nftMints = [mint1, mint2];
+    const { mint: mint3 } = await mintNft(
+      provider,
+      nftSymbol,
+      gameMaster.payer,
+      players[0].publicKey,
+      collectionMint
+    );
+    nftMints = [mint1, mint2, mint3];
 
     // Collection authority verifies that the NFT belongs to the collection
     await verifyCollection(provider, mint1, collectionMint, gameMaster.payer);
     await verifyCollection(provider, mint2, collectionMint, gameMaster.payer);
+    await verifyCollection(provider, mint3, collectionMint, gameMaster.payer);
 
     collectionInfo = {
       v2: {
@@ -135,7 +150,7 @@ describe("Participant action", () => {
     let state1 = await participants[0].getParticipantState();
     let state2 = await participants[1].getParticipantState();
 
-    expect(state1.actionPointsSpent).to.equal(pointsSpent);
+    expect(state1.actionPointsSpent.toNumber()).to.equal(pointsSpent);
     expect(state2.healthPoints).to.equal(750 + 5 * (defense + 50) - (100 + attack) * pointsSpent);
 
     const pointsSpent2 = 2;
@@ -146,4 +161,297 @@ describe("Participant action", () => {
       750 + 5 * (defense + 50) - (100 + attack) * pointsSpent + ((defense + 50) * pointsSpent2) / 2
     );
   });
+
+  it("emits the outcome of actions", async () => {
+    const program = participants[0].program;
+    let actionEvent: any;
+    let eliminatedEvent: any;
+    const listeners = [
+      program.addEventListener("ParticipantActionEvent", (e) => (actionEvent = e)),
+      program.addEventListener("ParticipantEliminatedEvent", (e) => (eliminatedEvent = e)),
+    ];
+    const healthPointsBefore = (await participants[1].getParticipantState()).healthPoints;
+
+    // Only the points needed to finish the target are spent
+    await participants[0].action(participants[1], { attack: {} }, 20);
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    await Promise.all(listeners.map((listener) => program.removeEventListener(listener)));
+
+    expect(actionEvent.nftMint.toBase58()).to.equal(nftMints[0].toBase58());
+    expect(actionEvent.targetNftMint.toBase58()).to.equal(nftMints[1].toBase58());
+    expect(actionEvent.actionPointsSpent).to.equal(Math.ceil(healthPointsBefore / (100 + attack)));
+    expect(actionEvent.healthPointsBefore).to.equal(healthPointsBefore);
+    expect(actionEvent.healthPointsAfter).to.equal(0);
+    expect(actionEvent.amount).to.equal(healthPointsBefore);
+    expect(actionEvent.killed).to.be.true;
+    expect(eliminatedEvent.nftMint.toBase58()).to.equal(nftMints[1].toBase58());
+    expect(eliminatedEvent.placement).to.equal(2);
+  });
+
+  describe("with defense mitigation", () => {
+    const combatConfig: CombatConfig = {
+      baseHealthPoints: 750,
+      healthPointsPerDefense: 5,
+      baseAttack: 100,
+      baseDefense: 50,
+      healDivisor: 2,
+      maxStatistics: 100,
+      defenseMitigation: 100,
+      minDamage: 1,
+      critChance: 0,
+      dodgeChance: 0,
+      critMultiplier: 20000,
+    };
+
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { combatConfig }
+      );
+      participants[0] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+        .join(nftMints[0], 100, 0);
+      participants[1] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[1], {}))
+        .join(nftMints[1], 0, 100);
+
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    });
+
+    it("defense reduces the damage", async () => {
+      await participants[0].action(participants[1], { attack: {} }, 1);
+      const state = await participants[1].getParticipantState();
+
+      // 200 attack against 150 defense
+      expect(state.healthPoints).to.equal(750 + 5 * 150 - Math.floor((200 * 100) / 250));
+    });
+
+    it("a kill doesn't waste action points", async () => {
+      const spentBefore = (await participants[0].getParticipantState()).actionPointsSpent;
+      await participants[0].action(participants[1], { attack: {} }, 1000);
+      const state = await participants[0].getParticipantState();
+
+      expect((await participants[1].getParticipantState()).alive).to.be.false;
+      expect(state.actionPointsSpent.sub(spentBefore).toNumber()).to.equal(
+        Math.ceil((1500 - 80) / 80)
+      );
+    });
+  });
+
+  describe("with shields, poison and scouting", () => {
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay
+      );
+      participants[0] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+        .join(nftMints[0], attack, defense);
+      participants[1] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[1], {}))
+        .join(nftMints[1], attack, defense);
+
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    });
+
+    it("can't attack or scout itself", async () => {
+      await expectRevert(
+        participants[0].action(participants[0], { attack: {} }, 1),
+        "InvalidTarget"
+      );
+      await expectRevert(
+        participants[0].action(participants[0], { scout: {} }, 10),
+        "InvalidTarget"
+      );
+    });
+
+    it("a shield absorbs damage first", async () => {
+      await participants[1].action(participants[1], { shield: {} }, 1);
+      const shielded = await participants[1].getParticipantState();
+      expect(shielded.shield).to.equal((defense + 50) / 2);
+      // The shield wears off after a day
+      expect(shielded.shieldUntil.toNumber()).to.be.greaterThan(Date.now() / 1000 + 86400 - 60);
+
+      await participants[0].action(participants[1], { attack: {} }, 1);
+      const state = await participants[1].getParticipantState();
+
+      expect(state.shield).to.equal(0);
+      expect(state.healthPoints).to.equal(
+        750 + 5 * (defense + 50) - (100 + attack) + (defense + 50) / 2
+      );
+      expect(state.actionPointsSpent.toNumber()).to.equal(1);
+    });
+
+    it("poison is dealt over time", async () => {
+      await participants[0].action(participants[1], { poison: {} }, 1);
+      const state = await participants[1].getParticipantState();
+
+      expect(state.poisonDamage).to.equal(100 + attack);
+      expect(state.poisonedUntil.sub(state.poisonResolvedAt).toNumber()).to.equal(86400);
+    });
+
+    it("scouting forces the next attack", async () => {
+      await participants[1].action(participants[0], { scout: {} }, 10);
+      const state = await participants[0].getParticipantState();
+
+      expect(state.tauntedBy.toString()).to.equal(participants[1].addresses.participant.toString());
+
+      await participants[0].action(participants[1], { attack: {} }, 1);
+      expect((await participants[0].getParticipantState()).tauntedBy).to.be.null;
+    });
+  });
+
+  describe("with a scout out of the battle", () => {
+    let fighters: Participant[];
+
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        3,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay
+      );
+      fighters = [
+        await battleground
+          .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+          .join(nftMints[0], attack, defense),
+        await battleground
+          .connect(new anchor.AnchorProvider(provider.connection, players[1], {}))
+          .join(nftMints[1], attack, defense),
+        await battleground
+          .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+          .join(nftMints[2], attack, defense),
+      ];
+
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+      await fighters[1].action(fighters[0], { scout: {} }, 10);
+    });
+
+    it("poison respects the taunt", async () => {
+      await expectRevert(fighters[0].action(fighters[2], { poison: {} }, 1), "MustAttackScout");
+    });
+
+    it("the taunt is dropped once the scout is eliminated", async () => {
+      await fighters[2].action(fighters[1], { attack: {} }, 100);
+      expect((await fighters[1].getParticipantState()).alive).to.be.false;
+
+      await fighters[0].action(fighters[2], { attack: {} }, 1);
+      expect((await fighters[0].getParticipantState()).tauntedBy).to.be.null;
+    });
+  });
+
+  describe("with a self-only heal policy", () => {
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { teamConfig: { teamMode: { freeForAll: {} }, healPolicy: HealPolicy.SelfOnly } }
+      );
+      participants[0] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+        .join(nftMints[0], attack, defense);
+      participants[1] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[1], {}))
+        .join(nftMints[1], attack, defense);
+
+      await battleground.start();
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    });
+
+    it("can't heal at full health", async () => {
+      await expectRevert(
+        participants[0].action(participants[0], { heal: {} }, 1),
+        "TargetAtFullHealth"
+      );
+    });
+
+    it("can only heal itself", async () => {
+      await participants[0].action(participants[1], { attack: {} }, 1);
+      await expectRevert(
+        participants[0].action(participants[1], { heal: {} }, 1),
+        "HealNotAllowed"
+      );
+
+      await participants[1].action(participants[1], { heal: {} }, 1);
+      const state = await participants[1].getParticipantState();
+
+      expect(state.healthPoints).to.equal(
+        750 + 5 * (defense + 50) - (100 + attack) + (defense + 50) / 2
+      );
+      expect(state.actionPointsSpent.toNumber()).to.equal(1);
+    });
+
+    it("can't target an eliminated participant", async () => {
+      await participants[0].action(participants[1], { attack: {} }, 1000);
+      await expectRevert(participants[1].action(participants[1], { heal: {} }, 1), "ConstraintRaw");
+      await expectRevert(
+        participants[0].action(participants[1], { attack: {} }, 1),
+        "TargetNotAlive"
+      );
+    });
+  });
+
+  describe("with a bank cap and a cooldown", () => {
+    const maxActionPointsBank = 10;
+
+    before(async () => {
+      battleground = await battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { actionConfig: { maxActionPointsBank, cooldown: new anchor.BN(3600) } }
+      );
+      participants[0] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[0], {}))
+        .join(nftMints[0], attack, defense);
+      participants[1] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, players[1], {}))
+        .join(nftMints[1], attack, defense);
+
+      await battleground.start();
+      // Earn more points than the bank can hold
+      await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    });
+
+    it("can't spend more than the bank", async () => {
+      await expectRevert(
+        participants[0].action(participants[1], { attack: {} }, maxActionPointsBank + 1),
+        "InsufficientActionPoints"
+      );
+    });
+
+    it("can't act again before the cooldown", async () => {
+      await participants[0].action(participants[1], { attack: {} }, 1);
+      await expectRevert(
+        participants[0].action(participants[1], { attack: {} }, 1),
+        "ActionOnCooldown"
+      );
+    });
+  });
 });
@@ -0,0 +1,149 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, CollectionInfo, CombatConfig, Participant } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+
+import { expect } from "chai";
+import keccak256 from "keccak256";
+
+describe("Randomness", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const secret = [...anchor.web3.Keypair.generate().publicKey.toBytes()];
+  let provider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participants: Participant[] = [];
+  let participantsCap = 2;
+  let entryFee = new anchor.BN(100);
+  let creatorFee = 100;
+  let actionPointsPerDay = 8640000;
+  let collectionInfo: CollectionInfo;
+  const combatConfig: CombatConfig = {
+    baseHealthPoints: 750,
+    healthPointsPerDefense: 5,
+    baseAttack: 100,
+    baseDefense: 50,
+    healDivisor: 2,
+    maxStatistics: 100,
+    defenseMitigation: 0,
+    minDamage: 1,
+    critChance: 10000,
+    dodgeChance: 0,
+    critMultiplier: 20000,
+  };
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, player], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, player.publicKey, 10000)).mint;
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+      player.publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, 100);
+
+    // Every attack is a critical hit
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { combatConfig }
+    );
+    for (let i = 0; i < participantsCap; i++) {
+      participants.push(
+        await battleground
+          .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+          .join(nftMints[i], 50, 50)
+      );
+    }
+  });
+
+  it("can't start without a committed seed", async () => {
+    await expectRevert(battleground.start(), "RandomnessNotCommitted");
+  });
+
+  it("can't be committed by a player", async () => {
+    await expectRevert(
+      battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .commitSeed([...keccak256(Buffer.from(secret))]),
+      "Unauthorized"
+    );
+  });
+
+  it("commit the seed and start the battle", async () => {
+    await battleground
+      .connect(new anchor.AnchorProvider(provider.connection, creator, {}))
+      .commitSeed([...keccak256(Buffer.from(secret))]);
+    await battleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+  });
+
+  it("can't act before the seed is revealed", async () => {
+    await expectRevert(
+      participants[0].action(participants[1], { attack: {} }, 1),
+      "RandomnessNotRevealed"
+    );
+  });
+
+  it("can't reveal another secret", async () => {
+    await expectRevert(
+      battleground
+        .connect(new anchor.AnchorProvider(provider.connection, creator, {}))
+        .revealSeed([...Buffer.alloc(32)]),
+      "InvalidSeed"
+    );
+  });
+
+  it("critical hits deal more damage", async () => {
+    await battleground
+      .connect(new anchor.AnchorProvider(provider.connection, creator, {}))
+      .revealSeed(secret);
+    expect((await battleground.getBattlegroundState()).seed).to.exist;
+
+    await participants[0].action(participants[1], { attack: {} }, 1);
+    const state = await participants[1].getParticipantState();
+
+    expect(state.healthPoints).to.equal(750 + 5 * 100 - 2 * 150);
+    expect((await participants[0].getParticipantState()).actionNonce.toNumber()).to.equal(1);
+  });
+
+  it("can't act without spending points", async () => {
+    await expectRevert(
+      participants[0].action(participants[1], { attack: {} }, 0),
+      "InsufficientActionPoints"
+    );
+    expect((await participants[0].getParticipantState()).actionNonce.toNumber()).to.equal(1);
+  });
+
+  it("only attacks consume a roll", async () => {
+    await participants[1].action(participants[1], { shield: {} }, 1);
+    expect((await participants[1].getParticipantState()).actionNonce.toNumber()).to.equal(0);
+  });
+});
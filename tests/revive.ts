@@ -0,0 +1,122 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, CollectionInfo, Participant, ReviveConfig } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+import { getAccount } from "@solana/spl-token";
+
+import { expect } from "chai";
+
+describe("Revive", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  let provider: anchor.AnchorProvider;
+  let playerProvider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participants: Participant[] = [];
+  let participantsCap = 3;
+  let entryFee = new anchor.BN(100);
+  let creatorFee = 100;
+  let actionPointsPerDay = 8640000;
+  let collectionInfo: CollectionInfo;
+  const reviveConfig: ReviveConfig = {
+    fee: new anchor.BN(50),
+    maxRevives: 1,
+    healthPoints: 5000,
+    minParticipants: 2,
+  };
+
+  const createBattleground = (reviveConfig: ReviveConfig) =>
+    battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { reviveConfig }
+    );
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+    playerProvider = new anchor.AnchorProvider(provider.connection, player, {});
+
+    await airdropWallets([gameMaster, creator, player], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, player.publicKey, 10000)).mint;
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+      player.publicKey,
+      player.publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, 100);
+  });
+
+  it("can't stay open within the paid ranks", async () => {
+    await expectRevert(
+      createBattleground({ ...reviveConfig, minParticipants: 0 }),
+      "InvalidParameter"
+    );
+  });
+
+  it("start a battle with revives", async () => {
+    battleground = await createBattleground(reviveConfig);
+    for (let i = 0; i < participantsCap; i++) {
+      participants.push(await battleground.connect(playerProvider).join(nftMints[i], 50, 50));
+    }
+
+    await battleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+  });
+
+  it("can't revive a living participant", async () => {
+    await expectRevert(participants[2].revive(), "ParticipantAlive");
+  });
+
+  it("revive an eliminated participant", async () => {
+    await participants[0].action(participants[2], { attack: {} }, 100);
+    const potAccount = await battleground.potAccount(battleground.addresses.authority);
+    const potBefore = Number((await getAccount(provider.connection, potAccount)).amount);
+
+    await participants[2].revive();
+    const state = await participants[2].getParticipantState();
+
+    expect(state.alive).to.be.true;
+    expect(state.revives).to.equal(1);
+    expect(state.healthPoints).to.equal((750 + 5 * 100) / 2);
+    expect((await battleground.getBattlegroundState()).participants).to.equal(3);
+    expect(Number((await getAccount(provider.connection, potAccount)).amount)).to.equal(
+      potBefore + reviveConfig.fee.toNumber()
+    );
+  });
+
+  it("revives are limited", async () => {
+    await participants[0].action(participants[2], { attack: {} }, 100);
+    await expectRevert(participants[2].revive(), "MaxRevivesReached");
+  });
+
+  it("revives close when few participants remain", async () => {
+    await participants[0].action(participants[1], { attack: {} }, 100);
+    await expectRevert(participants[1].revive(), "RevivesClosed");
+  });
+});
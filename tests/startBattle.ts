@@ -2,7 +2,7 @@ import * as anchor from "@project-serum/anchor";
 
 import { BattleRoyale, Battleground, BattlegroundStatus, CollectionInfo } from "../ts";
 import { airdropWallets, gameMaster } from "./common";
-import { mintCollection, mintToken } from "./utils";
+import { expectRevert, mintCollection, mintToken } from "./utils";
 
 import { expect } from "chai";
 
@@ -14,6 +14,7 @@ describe("Start a Battleground", () => {
   let provider: anchor.AnchorProvider;
   let potMint: anchor.web3.PublicKey;
   let nftMint: anchor.web3.PublicKey;
+  let nftMint2: anchor.web3.PublicKey;
   let battleRoyale: BattleRoyale;
   let battleground: Battleground;
   let fee: number;
@@ -35,8 +36,10 @@ describe("Start a Battleground", () => {
     // Create the collection
     const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
       player.publicKey,
+      player.publicKey,
     ]);
     nftMint = mints[0];
+    nftMint2 = mints[1];
 
     collectionInfo = {
       v2: {
@@ -76,4 +79,29 @@ describe("Start a Battleground", () => {
     expect(state.startTime.toString()).to.not.equal(new anchor.BN(0).toString());
     expect(state.status[BattlegroundStatus.Ongoing]).to.exist;
   });
+
+  it("force start a battle before it is full", async () => {
+    const battleground2 = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap + 1,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { minParticipants: 1 }
+    );
+    await battleground2
+      .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+      .join(nftMint2, 50, 50);
+
+    // Only the creator or the game master can start a battle that is not full
+    await expectRevert(battleground2.start(), "CannotStartBattle");
+    await battleground2
+      .connect(new anchor.AnchorProvider(provider.connection, creator, {}))
+      .start();
+    const state = await battleground2.getBattlegroundState();
+
+    expect(state.status[BattlegroundStatus.Ongoing]).to.exist;
+  });
 });
@@ -0,0 +1,174 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, CollectionInfo, Participant, StormConfig } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { mintCollection, mintToken } from "./utils";
+
+import { expect } from "chai";
+
+describe("Storm", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  let provider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participants: Participant[] = [];
+  let participantsCap = 2;
+  let entryFee = new anchor.BN(100);
+  let creatorFee = 100;
+  let actionPointsPerDay = 8640000;
+  let collectionInfo: CollectionInfo;
+  // 100 health points per second once idle
+  const stormConfig: StormConfig = {
+    gracePeriod: new anchor.BN(0),
+    damagePerDay: 100 * 86400,
+    idlePeriod: new anchor.BN(60),
+  };
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, player], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, player.publicKey, 10000)).mint;
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+      player.publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, 100);
+
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { stormConfig }
+    );
+    for (let i = 0; i < participantsCap; i++) {
+      participants.push(
+        await battleground
+          .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+          .join(nftMints[i], 50, 50)
+      );
+    }
+
+    await battleground.start();
+  });
+
+  it("acting shelters from the storm", async () => {
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    // The damage taken before acting is dealt first
+    await participants[0].action(participants[1], { attack: {} }, 1);
+    const healthPoints = (await participants[0].getParticipantState()).healthPoints;
+
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    await battleground.applyStorm(participants[0]);
+
+    expect((await participants[0].getParticipantState()).healthPoints).to.equal(healthPoints);
+  });
+
+  it("the storm eliminates idle participants", async () => {
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 15000));
+    await battleground.applyStorm(participants[1]);
+
+    expect((await participants[1].getParticipantState()).alive).to.be.false;
+    expect((await battleground.getBattlegroundState()).participants).to.equal(1);
+  });
+
+  it("spares the last survivor, who can finish the battle", async () => {
+    const lastBattleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { stormConfig }
+    );
+    const lastParticipants: Participant[] = [];
+    for (let i = 0; i < participantsCap; i++) {
+      lastParticipants.push(
+        await lastBattleground
+          .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+          .join(nftMints[i], 50, 50)
+      );
+    }
+    await lastBattleground.start();
+
+    // Both participants stay idle long enough for the storm to finish them
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 15000));
+    await lastBattleground.applyStorm(lastParticipants[1]);
+    await lastBattleground.applyStorm(lastParticipants[0]);
+
+    expect((await lastParticipants[1].getParticipantState()).alive).to.be.false;
+    expect((await lastParticipants[0].getParticipantState()).alive).to.be.true;
+    expect((await lastBattleground.getBattlegroundState()).participants).to.equal(1);
+
+    await lastParticipants[0].finishBattle();
+    expect((await lastBattleground.getBattlegroundState()).round).to.equal(1);
+  });
+
+  it("ranks timeout survivors on their health points at the deadline", async () => {
+    const timeoutBattleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { stormConfig, maxDuration: new anchor.BN(3) }
+    );
+    const timeoutParticipants: Participant[] = [];
+    for (let i = 0; i < participantsCap; i++) {
+      timeoutParticipants.push(
+        await timeoutBattleground
+          .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+          .join(nftMints[i], 50, 50)
+      );
+    }
+    await timeoutBattleground.start();
+
+    // The first participant shelters itself, the storm damage of the idle one is not dealt yet
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+    await timeoutParticipants[0].action(timeoutParticipants[0], { heal: {} }, 1);
+    const healthPoints = await Promise.all(
+      timeoutParticipants.map(async (e) => (await e.getParticipantState()).healthPoints)
+    );
+    expect(healthPoints[1]).to.be.greaterThan(healthPoints[0]);
+
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 3000));
+    await timeoutBattleground.finishByTimeout();
+    await timeoutBattleground.rankSurvivor(timeoutParticipants[1]);
+    await timeoutBattleground.rankSurvivor(timeoutParticipants[0], timeoutParticipants[1]);
+    expect((await timeoutParticipants[1].getParticipantState()).healthPoints).to.be.lessThan(
+      healthPoints[0]
+    );
+
+    await timeoutBattleground.settleSurvivors(timeoutParticipants);
+    expect((await timeoutParticipants[0].getParticipantState()).placement).to.equal(1);
+    expect((await timeoutParticipants[1].getParticipantState()).placement).to.equal(2);
+  });
+});
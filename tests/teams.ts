@@ -0,0 +1,187 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, CollectionInfo, Participant } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintToken } from "./utils";
+import { getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+
+import { expect } from "chai";
+
+describe("Team battles", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  let provider: anchor.AnchorProvider;
+  let playerProvider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participants: Participant[] = [];
+  let participantsCap = 3;
+  let entryFee = new anchor.BN(100);
+  let fee = 100;
+  let creatorFee = 100;
+  let actionPointsPerDay = 8640000;
+  let collectionInfo: CollectionInfo;
+
+  const playerBalance = async () =>
+    Number(
+      (
+        await getAccount(
+          provider.connection,
+          await getAssociatedTokenAddress(potMint, player.publicKey)
+        )
+      ).amount
+    );
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+    playerProvider = new anchor.AnchorProvider(provider.connection, player, {});
+
+    await airdropWallets([gameMaster, creator, player], provider);
+
+    // Create the pot token and mint some to the player
+    potMint = (await mintToken(provider, creator.payer, player.publicKey, 10000)).mint;
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+      player.publicKey,
+      player.publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, fee);
+  });
+
+  it("can't have a single team", async () => {
+    await expectRevert(
+      battleRoyale.createBattleground(
+        collectionInfo,
+        potMint,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay,
+        { teamConfig: { teamMode: { playerChoice: { teams: 1 } } } }
+      ),
+      "InvalidParameter"
+    );
+  });
+
+  it("players pick their team", async () => {
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { teamConfig: { teamMode: { playerChoice: { teams: 2 } } } }
+    );
+
+    await expectRevert(
+      battleground.connect(playerProvider).join(nftMints[0], 50, 50, null, null, null, 2),
+      "InvalidTeam"
+    );
+
+    const teams = [0, 0, 1];
+    for (let i = 0; i < participantsCap; i++) {
+      participants.push(
+        await battleground
+          .connect(playerProvider)
+          .join(nftMints[i], 50, 50, null, null, null, teams[i])
+      );
+    }
+
+    expect((await participants[2].getParticipantState()).team).to.equal(1);
+    expect((await battleground.getBattlegroundState()).teamSurvivors).to.deep.equal([2, 1]);
+
+    await battleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+  });
+
+  it("can't attack a teammate", async () => {
+    await expectRevert(participants[0].action(participants[1], { attack: {} }, 1), "FriendlyFire");
+  });
+
+  it("can't finish while two teams survive", async () => {
+    await expectRevert(battleground.finishTeamBattle(), "InvalidSurvivors");
+  });
+
+  it("the surviving team splits the pot", async () => {
+    await participants[0].action(participants[2], { attack: {} }, 100);
+    expect((await battleground.getBattlegroundState()).teamSurvivors).to.deep.equal([2, 0]);
+
+    const balanceBefore = await playerBalance();
+    await battleground.finishTeamBattle();
+    await battleground.settleSurvivors(participants.slice(0, 2));
+    await participants[0].claimPrize();
+    await participants[1].claimPrize();
+
+    const potAmount =
+      participantsCap * entryFee.toNumber() -
+      (participantsCap * entryFee.toNumber() * (fee + creatorFee)) / 10000;
+    expect((await playerBalance()) - balanceBefore).to.equal(potAmount);
+    expect((await battleground.getBattlegroundState()).round).to.equal(1);
+  });
+
+  it("the team with the most survivors wins by timeout", async () => {
+    const timeoutBattleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay,
+      { maxDuration: new anchor.BN(3), teamConfig: { teamMode: { playerChoice: { teams: 2 } } } }
+    );
+    const teams = [0, 1, 1];
+    const timeoutParticipants: Participant[] = [];
+    for (let i = 0; i < participantsCap; i++) {
+      timeoutParticipants.push(
+        await timeoutBattleground
+          .connect(playerProvider)
+          .join(nftMints[i], 50, 50, null, null, null, teams[i])
+      );
+    }
+    await timeoutBattleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 4000));
+
+    await timeoutBattleground.finishByTimeout();
+    await timeoutBattleground.rankSurvivors(timeoutParticipants);
+    const state = await timeoutBattleground.getBattlegroundState();
+    expect(state.settlement.winningTeams).to.equal(1 << 1);
+    expect(state.settlement.winners).to.equal(2);
+
+    // The winners are settled after the other survivors
+    await expectRevert(
+      timeoutBattleground.settleSurvivor(timeoutParticipants[1]),
+      "InvalidSurvivors"
+    );
+    await timeoutBattleground.settleSurvivors(timeoutParticipants);
+
+    expect((await timeoutParticipants[0].getParticipantState()).alive).to.be.false;
+    expect((await timeoutParticipants[0].getParticipantState()).placement).to.equal(3);
+    for (const participant of timeoutParticipants.slice(1)) {
+      const participantState = await participant.getParticipantState();
+      expect(participantState.placement).to.equal(1);
+      expect(participantState.prize.toNumber()).to.be.greaterThan(0);
+    }
+    expect((await timeoutBattleground.getBattlegroundState()).round).to.equal(1);
+  });
+});
@@ -0,0 +1,133 @@
+import * as anchor from "@project-serum/anchor";
+
+import { BattleRoyale, Battleground, CollectionInfo, Participant } from "../ts";
+import { airdropWallets, gameMaster } from "./common";
+import { expectRevert, mintCollection, mintTokenWithTransferFee } from "./utils";
+
+import { expect } from "chai";
+
+describe("Token-2022", () => {
+  const nftSymbol = "DAPE";
+
+  const creator = new anchor.Wallet(anchor.web3.Keypair.generate());
+  const player = new anchor.Wallet(anchor.web3.Keypair.generate());
+  let provider: anchor.AnchorProvider;
+  let potMint: anchor.web3.PublicKey;
+  let playerPotAccount: anchor.web3.PublicKey;
+  let nftMints: anchor.web3.PublicKey[];
+  let battleRoyale: BattleRoyale;
+  let battleground: Battleground;
+  let participantsCap = 2;
+  let participants: Participant[] = Array(participantsCap);
+  let fee = 100;
+  let entryFee = new anchor.BN(10000);
+  let creatorFee = 100;
+  let transferFeeBasisPoints = 100;
+  let actionPointsPerDay = 8640000;
+  let collectionInfo: CollectionInfo;
+
+  // Token-2022 rounds the withheld fee up
+  const transferFee = (amount: number) => Math.ceil((amount * transferFeeBasisPoints) / 10000);
+  const potFee = entryFee.toNumber() - (entryFee.toNumber() * (fee + creatorFee)) / 10000;
+
+  const tokenBalance = async (account: anchor.web3.PublicKey) =>
+    Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
+
+  before(async () => {
+    provider = new anchor.AnchorProvider(anchor.getProvider().connection, gameMaster, {});
+
+    await airdropWallets([gameMaster, creator, player], provider);
+
+    // Create the pot token with a transfer fee and mint some to the player
+    const pot = await mintTokenWithTransferFee(
+      provider,
+      creator.payer,
+      player.publicKey,
+      1000000,
+      transferFeeBasisPoints
+    );
+    potMint = pot.mint;
+    playerPotAccount = pot.tokenAccount;
+
+    // Create the collection
+    const { mints, collectionMint } = await mintCollection(provider, nftSymbol, gameMaster.payer, [
+      player.publicKey,
+      player.publicKey,
+    ]);
+    nftMints = mints;
+
+    collectionInfo = {
+      v2: {
+        collectionMint,
+      },
+    };
+
+    battleRoyale = new BattleRoyale(provider);
+
+    // Initialize BattleRoyale
+    await battleRoyale.initialize(gameMaster.publicKey, gameMaster.publicKey, fee);
+  });
+
+  it("can't use a token account as the pot mint", async () => {
+    await expectRevert(
+      battleRoyale.createBattleground(
+        collectionInfo,
+        playerPotAccount,
+        participantsCap,
+        entryFee,
+        creator.publicKey,
+        creatorFee,
+        actionPointsPerDay
+      ),
+      "InvalidMint"
+    );
+  });
+
+  it("contributions only count what the pot received", async () => {
+    battleground = await battleRoyale.createBattleground(
+      collectionInfo,
+      potMint,
+      participantsCap,
+      entryFee,
+      creator.publicKey,
+      creatorFee,
+      actionPointsPerDay
+    );
+    for (let i = 0; i < participantsCap; i++) {
+      participants[i] = await battleground
+        .connect(new anchor.AnchorProvider(provider.connection, player, {}))
+        .join(nftMints[i], 50, 50);
+    }
+
+    const received = potFee - transferFee(potFee);
+    for (const participant of participants) {
+      expect((await participant.getParticipantState()).contribution.toNumber()).to.equal(received);
+    }
+    expect(await tokenBalance(await battleground.potAccount(battleground.addresses.authority))).to
+      .equal(participantsCap * received);
+  });
+
+  it("the prize pool is what the pot holds", async () => {
+    await battleground.start();
+    await new Promise((resolve) => setTimeout(() => resolve(undefined), 1000));
+
+    const received = potFee - transferFee(potFee);
+    expect((await battleground.getBattlegroundState()).prizePool.toNumber()).to.equal(
+      participantsCap * received
+    );
+  });
+
+  it("the winner receives the pot minus the transfer fee", async () => {
+    await participants[0].action(participants[1], { attack: {} }, 100);
+
+    const potAmount = (await battleground.getBattlegroundState()).prizePool.toNumber();
+    const balanceBefore = await tokenBalance(playerPotAccount);
+    await participants[0].finishBattle();
+
+    expect((await tokenBalance(playerPotAccount)) - balanceBefore).to.equal(
+      potAmount - transferFee(potAmount)
+    );
+    expect(await tokenBalance(await battleground.potAccount(battleground.addresses.authority))).to
+      .equal(0);
+  });
+});
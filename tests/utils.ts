@@ -9,7 +9,13 @@ import {
   createVerifyCollectionInstruction,
 } from "@metaplex-foundation/mpl-token-metadata";
 import { assert, expect } from "chai";
-import { createAssociatedTokenAccount, createMint, mintTo, mintToChecked } from "@solana/spl-token";
+import {
+  createAssociatedTokenAccount,
+  createInitializeMintInstruction,
+  createMint,
+  mintTo,
+  mintToChecked,
+} from "@solana/spl-token";
 
 import MerkleTree from "merkletreejs";
 import keccak256 from "keccak256";
@@ -27,6 +33,22 @@ export const getMerkleProof = (tree: MerkleTree, mint: anchor.web3.PublicKey) =>
   return proof.map((x) => [...x]);
 };
 
+export const getStatLeaf = (mint: anchor.web3.PublicKey, attack: number, defense: number) => {
+  const stats = Buffer.alloc(8);
+  stats.writeUInt32LE(attack, 0);
+  stats.writeUInt32LE(defense, 4);
+  return keccak256(Buffer.concat([mint.toBuffer(), stats]));
+};
+
+export const getStatTable = (
+  stats: { mint: anchor.web3.PublicKey; attack: number; defense: number }[]
+) => {
+  const leaves = stats.map((x) => getStatLeaf(x.mint, x.attack, x.defense));
+  const tree = new MerkleTree(leaves, keccak256, { sort: true });
+  const root = tree.getRoot();
+  return { root: [...root], tree };
+};
+
 export const getTokenMetadata = (tokenMint: anchor.web3.PublicKey) => {
   const [tokenMetadataAddress, bump] = anchor.web3.PublicKey.findProgramAddressSync(
     [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), tokenMint.toBuffer()],
@@ -56,7 +78,14 @@ export const mintNft = async (
   collectionMint?: anchor.web3.PublicKey,
   v1: boolean = false
 ) => {
-  const mint = await createMint(provider.connection, creator, creator.publicKey, null, 0);
+  // The master edition takes over the freeze authority, to freeze soft-staked NFTs
+  const mint = await createMint(
+    provider.connection,
+    creator,
+    creator.publicKey,
+    creator.publicKey,
+    0
+  );
 
   const tokenAccount = await createAssociatedTokenAccount(
     provider.connection,
@@ -175,6 +204,82 @@ export const mintToken = async (
   return { mint, tokenAccount };
 };
 
+// This version of spl-token predates Token-2022, its extension instructions are built by hand
+export const TOKEN_2022_PROGRAM_ID = new anchor.web3.PublicKey(
+  "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"
+);
+
+// Token-2022 mint withholding a share of every transfer, in basis points
+export const mintTokenWithTransferFee = async (
+  provider: anchor.Provider,
+  creator: anchor.web3.Signer,
+  destination: anchor.web3.PublicKey,
+  initialSupply: number | bigint,
+  transferFeeBasisPoints: number,
+  decimals: number = 8
+) => {
+  const mint = anchor.web3.Keypair.generate();
+  // Base mint padded to the size of a token account, the account type and the fee extension
+  const mintLen = 165 + 1 + 4 + 108;
+
+  // Both the fee config and withdraw authorities are the creator, with no maximum fee
+  const data = Buffer.alloc(2 + 2 * 33 + 2 + 8);
+  data.writeUInt8(26, 0);
+  data.writeUInt8(0, 1);
+  data.writeUInt8(1, 2);
+  creator.publicKey.toBuffer().copy(data, 3);
+  data.writeUInt8(1, 35);
+  creator.publicKey.toBuffer().copy(data, 36);
+  data.writeUInt16LE(transferFeeBasisPoints, 68);
+  data.writeBigUInt64LE(BigInt("18446744073709551615"), 70);
+
+  const transaction = new anchor.web3.Transaction().add(
+    anchor.web3.SystemProgram.createAccount({
+      fromPubkey: creator.publicKey,
+      newAccountPubkey: mint.publicKey,
+      space: mintLen,
+      lamports: await provider.connection.getMinimumBalanceForRentExemption(mintLen),
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    new anchor.web3.TransactionInstruction({
+      programId: TOKEN_2022_PROGRAM_ID,
+      keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+      data,
+    }),
+    createInitializeMintInstruction(
+      mint.publicKey,
+      decimals,
+      creator.publicKey,
+      null,
+      TOKEN_2022_PROGRAM_ID
+    )
+  );
+  await provider.sendAndConfirm(transaction, [creator, mint]);
+
+  const tokenAccount = await createAssociatedTokenAccount(
+    provider.connection,
+    creator,
+    mint.publicKey,
+    destination,
+    undefined,
+    TOKEN_2022_PROGRAM_ID
+  );
+
+  await mintTo(
+    provider.connection,
+    creator,
+    mint.publicKey,
+    tokenAccount,
+    creator,
+    initialSupply,
+    [],
+    undefined,
+    TOKEN_2022_PROGRAM_ID
+  );
+
+  return { mint: mint.publicKey, tokenAccount };
+};
+
 export const verifyCollection = async (
   provider: anchor.AnchorProvider,
   nftMint: anchor.web3.PublicKey,
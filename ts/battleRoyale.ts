@@ -1,7 +1,12 @@
 import * as anchor from "@project-serum/anchor";
 
 import { BATTLE_ROYALE_PROGRAM_ID, BATTLE_ROYALE_STATE_SEEDS } from "./constants";
-import { BattleRoyaleAccount, CollectionInfo } from "./types";
+import {
+  BattleRoyaleAccount,
+  BattlegroundOptions,
+  CollectionInfo,
+  PotKind,
+} from "./types";
 
 import BattleRoyaleIdl from "../target/idl/battle_royale_program.json";
 import { BattleRoyaleProgram } from "../target/types/battle_royale_program";
@@ -46,17 +51,24 @@ class BattleRoyale {
     creator: anchor.web3.PublicKey,
     creatorFee: number,
     actionPointsPerDay: number,
-    whitelistRoot: number[] | null = null
+    options: BattlegroundOptions = {}
   ) {
     const id = (await this.getBattleRoyaleState()).lastBattlegroundId.toNumber();
-    const battleground = new Battleground(this, id, potMint, creator, this.program.provider);
+    const battleground = new Battleground(
+      this,
+      id,
+      potMint,
+      creator,
+      this.program.provider,
+      options.potKind ?? PotKind.Token
+    );
     await battleground.create(
       collectionInfo,
       participantsCap,
       entryFee,
       creatorFee,
       actionPointsPerDay,
-      whitelistRoot
+      options
     );
     return battleground;
   }
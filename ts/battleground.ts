@@ -9,9 +9,11 @@ import BattleRoyale, { BattleRoyaleAddresses } from "./battleRoyale";
 
 import BattleRoyaleIdl from "../target/idl/battle_royale_program.json";
 import { BattleRoyaleProgram } from "../target/types/battle_royale_program";
-import { CollectionInfo } from "./types";
+import { BattlegroundOptions, CollectionInfo, HealPolicy, PotKind } from "./types";
 import Participant from "./participant";
+import { PROGRAM_ID as METADATA_PROGRAM_ID } from "@metaplex-foundation/mpl-token-metadata";
 import { Program } from "@project-serum/anchor";
+import { getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
 
 export interface BattlegroundAddresses extends BattleRoyaleAddresses {
   battleRoyale: anchor.web3.PublicKey;
@@ -25,6 +27,8 @@ class Battleground {
   program: Program<BattleRoyaleProgram>;
   battleRoyale: BattleRoyale;
   id: number;
+  potKind: PotKind;
+  potTokenProgram?: anchor.web3.PublicKey;
   addresses: BattlegroundAddresses;
 
   constructor(
@@ -32,11 +36,13 @@ class Battleground {
     id: number,
     potMint: anchor.web3.PublicKey,
     creator: anchor.web3.PublicKey,
-    provider: anchor.Provider
+    provider: anchor.Provider,
+    potKind: PotKind = PotKind.Token
   ) {
     this.connect(provider);
     this.battleRoyale = battleRoyale;
     this.id = id;
+    this.potKind = potKind;
     this.addresses = {
       ...battleRoyale.addresses,
       authority: anchor.web3.PublicKey.findProgramAddressSync(
@@ -58,8 +64,9 @@ class Battleground {
     entryFee: anchor.BN,
     creatorFee: number,
     actionPointsPerDay: number,
-    whitelistRoot: number[] | null = null
+    options: BattlegroundOptions = {}
   ) {
+    const { teamConfig, actionConfig, custodyConfig } = options;
     const tx = await this.program.methods
       .createBattleground(
         collectionInfo as any,
@@ -67,8 +74,42 @@ class Battleground {
         entryFee,
         this.addresses.creator,
         creatorFee,
-        actionPointsPerDay,
-        whitelistRoot
+        {
+          actionPointsPerDay,
+          whitelistRoot: options.whitelistRoot ?? null,
+          maxDuration: options.maxDuration ?? null,
+          payoutTable: options.payoutTable ?? [],
+          minParticipants: options.minParticipants ?? participantsCap,
+          scheduledStart: options.scheduledStart ?? null,
+          feesRefundable: options.feesRefundable ?? false,
+          championFreeEntry: options.championFreeEntry ?? false,
+          potKind: { [this.potKind]: {} },
+          statsSource: options.statsSource ?? { declared: {} },
+          combatConfig: options.combatConfig ?? null,
+          teamConfig: teamConfig
+            ? {
+                teamMode: teamConfig.teamMode,
+                friendlyFire: teamConfig.friendlyFire ?? false,
+                healPolicy: { [teamConfig.healPolicy ?? HealPolicy.Any]: {} },
+              }
+            : null,
+          reviveConfig: options.reviveConfig ?? null,
+          stormConfig: options.stormConfig ?? null,
+          actionConfig: actionConfig
+            ? {
+                maxActionPointsBank: actionConfig.maxActionPointsBank ?? null,
+                cooldown: actionConfig.cooldown ?? null,
+              }
+            : null,
+          custodyConfig: custodyConfig
+            ? {
+                nftCustody: { [custodyConfig.nftCustody]: {} },
+                forfeitRecipient: custodyConfig.forfeitRecipient
+                  ? { [custodyConfig.forfeitRecipient]: {} }
+                  : null,
+              }
+            : null,
+        } as any
       )
       .accounts({
         signer: this.program.provider.publicKey,
@@ -84,17 +125,101 @@ class Battleground {
     attack: number,
     defense: number,
     collectionWhitelistProof: number[][] | null = null,
-    holderWhitelistProof: number[][] | null = null
+    holderWhitelistProof: number[][] | null = null,
+    statsProof: number[][] | null = null,
+    team: number = 0
   ) {
     const participant = new Participant(this, nft, this.program.provider);
-    await participant.join(attack, defense, collectionWhitelistProof, holderWhitelistProof);
+    await participant.join(
+      attack,
+      defense,
+      collectionWhitelistProof,
+      holderWhitelistProof,
+      statsProof,
+      team
+    );
     return participant;
   }
 
   async start() {
+    const devFund = (await this.battleRoyale.getBattleRoyaleState()).devFund;
+
     const tx = await this.program.methods
       .startBattle()
       .accounts({
+        signer: this.program.provider.publicKey,
+        devFund,
+        battleRoyale: this.addresses.battleRoyale,
+        authority: this.addresses.authority,
+        creator: this.addresses.creator,
+        battleground: this.addresses.battleground,
+        potMint: this.addresses.potMint,
+        potAccount: await this.potAccount(this.addresses.authority),
+        tokenProgram: await this.getPotTokenProgram(),
+        devAccount: await this.potAccount(devFund),
+        creatorAccount: await this.potAccount(this.addresses.creator),
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async cancel() {
+    const tx = await this.program.methods
+      .cancelBattleground()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        battleground: this.addresses.battleground,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async commitSeed(seedHash: number[]) {
+    const tx = await this.program.methods
+      .commitSeed(seedHash)
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        battleground: this.addresses.battleground,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async revealSeed(secret: number[]) {
+    const tx = await this.program.methods
+      .revealSeed(secret)
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        battleground: this.addresses.battleground,
+        recentSlothashes: anchor.web3.SYSVAR_SLOT_HASHES_PUBKEY,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async applyStorm(participant: Participant) {
+    const tx = await this.program.methods
+      .applyStorm()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleground: this.addresses.battleground,
+        participant: participant.addresses.participant,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async finishByTimeout() {
+    const tx = await this.program.methods
+      .finishBattleByTimeout()
+      .accounts({
+        signer: this.program.provider.publicKey,
         battleRoyale: this.addresses.battleRoyale,
         battleground: this.addresses.battleground,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
@@ -103,6 +228,175 @@ class Battleground {
     await this.program.provider.connection.confirmTransaction(tx);
   }
 
+  // The previous survivor is the last ranked one with fewer health points, if any
+  async rankSurvivor(survivor: Participant, previous: Participant | null = null) {
+    const next = previous
+      ? (await previous.getParticipantState()).nextRanked
+      : (await this.getBattlegroundState()).settlement.lowest;
+    const remainingAccounts = [previous?.addresses.participant, next]
+      .filter((e) => e)
+      .map((e) => ({ pubkey: e, isSigner: false, isWritable: true }));
+
+    const tx = await this.program.methods
+      .rankSurvivor(previous?.addresses.participant ?? null)
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        battleground: this.addresses.battleground,
+        survivor: survivor.addresses.participant,
+      })
+      .remainingAccounts(remainingAccounts)
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  // Ranking from the most health points puts each survivor first, without a previous one
+  // Health points are taken as stored, pending poison or storm damage can call for another order
+  async rankSurvivors(survivors: Participant[]) {
+    const states = await Promise.all(survivors.map((e) => e.getParticipantState()));
+    const order = survivors
+      .map((survivor, i) => ({ survivor, healthPoints: states[i].healthPoints }))
+      .sort((a, b) => b.healthPoints - a.healthPoints);
+    for (const { survivor } of order) {
+      await this.rankSurvivor(survivor);
+    }
+  }
+
+  async settleSurvivor(survivor: Participant) {
+    const owner = (await survivor.getParticipantState()).owner;
+    const tx = await this.program.methods
+      .settleSurvivor()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        authority: this.addresses.authority,
+        battleground: this.addresses.battleground,
+        survivor: survivor.addresses.participant,
+        owner,
+        potMint: this.addresses.potMint,
+        potAccount: await this.potAccount(this.addresses.authority),
+        nftMint: survivor.nft,
+        ownerNftTokenAccount: await survivor.nftTokenAccount(owner),
+        nftEdition: survivor.nftEdition,
+        nftVault: await survivor.nftTokenAccount(this.addresses.authority),
+        nftTokenProgram: survivor.nftTokenProgram,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  // Free-for-all survivors are settled in ranking order, as the battleground points to the next one
+  // Team battle survivors are settled after those outside the winning teams
+  async settleSurvivors(survivors: Participant[]) {
+    const state = await this.getBattlegroundState();
+    if (state.teamConfig.teamMode.freeForAll) {
+      for (let i = 0; i < survivors.length; i++) {
+        const next = (await this.getBattlegroundState()).settlement.next;
+        await this.settleSurvivor(survivors.find((e) => e.addresses.participant.equals(next)));
+      }
+      return;
+    }
+
+    const teams = await Promise.all(
+      survivors.map(async (e) => (await e.getParticipantState()).team)
+    );
+    const isWinner = (i: number) => (state.settlement.winningTeams & (1 << teams[i])) !== 0;
+    const order = survivors
+      .map((_, i) => i)
+      .sort((a, b) => Number(isWinner(a)) - Number(isWinner(b)));
+    for (const i of order) {
+      await this.settleSurvivor(survivors[i]);
+    }
+  }
+
+  async finishTeamBattle() {
+    const tx = await this.program.methods
+      .finishTeamBattle()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        battleground: this.addresses.battleground,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  // The winner is only needed when forfeited NFTs go to the winners of the round
+  async claimForfeitedNfts(forfeited: Participant[], winner: Participant | null = null) {
+    const state = await this.getBattlegroundState();
+    let recipient: anchor.web3.PublicKey | null = null;
+    // The NFTs forfeited in a round without winners go to the creator
+    if (state.winnerlessForfeits > 0 || state.custodyConfig.forfeitRecipient?.creator) {
+      recipient = state.creator;
+    } else if (state.custodyConfig.forfeitRecipient?.winner) {
+      recipient = (await winner.getParticipantState()).owner;
+    }
+
+    const forfeitedAccounts: anchor.web3.AccountMeta[] = winner
+      ? [{ pubkey: winner.addresses.participant, isSigner: false, isWritable: true }]
+      : [];
+    for (const participant of forfeited) {
+      forfeitedAccounts.push(
+        { pubkey: participant.addresses.participant, isSigner: false, isWritable: true },
+        {
+          pubkey: (await participant.getParticipantState()).owner,
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: participant.nft, isSigner: false, isWritable: true },
+        {
+          pubkey: await participant.nftTokenAccount(this.addresses.authority),
+          isSigner: false,
+          isWritable: true,
+        }
+      );
+      if (recipient) {
+        forfeitedAccounts.push({
+          pubkey: await participant.nftTokenAccount(recipient),
+          isSigner: false,
+          isWritable: true,
+        });
+      }
+    }
+
+    const tx = await this.program.methods
+      .claimForfeitedNfts()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        authority: this.addresses.authority,
+        battleground: this.addresses.battleground,
+        recipient: recipient ?? this.addresses.authority,
+        nftTokenProgram: forfeited[0].nftTokenProgram,
+      })
+      .remainingAccounts(forfeitedAccounts)
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async potAccount(owner: anchor.web3.PublicKey) {
+    if (this.potKind === PotKind.Native) {
+      return owner;
+    }
+    return await getAssociatedTokenAddress(
+      this.addresses.potMint,
+      owner,
+      true,
+      await this.getPotTokenProgram()
+    );
+  }
+
+  // The pot mint can belong to either the original token program or Token-2022
+  async getPotTokenProgram() {
+    if (!this.potTokenProgram) {
+      this.potTokenProgram = (
+        await this.program.provider.connection.getAccountInfo(this.addresses.potMint)
+      ).owner;
+    }
+    return this.potTokenProgram;
+  }
+
   async getBattlegroundState() {
     return await this.program.account.battlegroundState.fetch(this.addresses.battleground);
   }
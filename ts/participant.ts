@@ -8,7 +8,8 @@ import BattleRoyaleIdl from "../target/idl/battle_royale_program.json";
 import { BattleRoyaleProgram } from "../target/types/battle_royale_program";
 import { Program } from "@project-serum/anchor";
 import { getAssociatedTokenAddress } from "@solana/spl-token";
-import { getTokenMetadata } from "./utils";
+import { getTokenEdition, getTokenMetadata } from "./utils";
+import { PROGRAM_ID as METADATA_PROGRAM_ID } from "@metaplex-foundation/mpl-token-metadata";
 
 export interface ParticipantAddresses extends BattlegroundAddresses {
   battleRoyale: anchor.web3.PublicKey;
@@ -23,6 +24,8 @@ class Participant {
   battleground: Battleground;
   nft: anchor.web3.PublicKey;
   nftMetadata: anchor.web3.PublicKey;
+  nftEdition: anchor.web3.PublicKey;
+  nftTokenProgram?: anchor.web3.PublicKey;
   addresses: ParticipantAddresses;
 
   constructor(battleground: Battleground, nft: anchor.web3.PublicKey, provider: anchor.Provider) {
@@ -30,6 +33,7 @@ class Participant {
     this.battleground = battleground;
     this.nft = nft;
     this.nftMetadata = getTokenMetadata(this.nft);
+    this.nftEdition = getTokenEdition(this.nft);
     this.addresses = {
       ...battleground.addresses,
       participant: anchor.web3.PublicKey.findProgramAddressSync(
@@ -43,34 +47,27 @@ class Participant {
     attack: number,
     defense: number,
     collectionWhitelistProof: number[][] | null = null,
-    holderWhitelistProof: number[][] | null = null
+    holderWhitelistProof: number[][] | null = null,
+    statsProof: number[][] | null = null,
+    team: number = 0
   ) {
     const gameMaster = (await this.battleground.battleRoyale.getBattleRoyaleState()).gameMaster;
 
-    const potAccount = await getAssociatedTokenAddress(
-      this.addresses.potMint,
-      this.addresses.authority,
-      true
-    );
-    const devAccount = await getAssociatedTokenAddress(this.addresses.potMint, gameMaster, true);
-    const creatorAccount = await getAssociatedTokenAddress(
-      this.addresses.potMint,
-      this.addresses.creator,
-      true
-    );
-    const playerAccount = await getAssociatedTokenAddress(
-      this.addresses.potMint,
-      this.program.provider.publicKey,
-      true
-    );
-    const playerNftTokenAccount = await getAssociatedTokenAddress(
-      this.nft,
-      this.program.provider.publicKey,
-      true
-    );
+    const potAccount = await this.battleground.potAccount(this.addresses.authority);
+    const devAccount = await this.battleground.potAccount(gameMaster);
+    const creatorAccount = await this.battleground.potAccount(this.addresses.creator);
+    const playerAccount = await this.battleground.potAccount(this.program.provider.publicKey);
+    const playerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
 
     const tx = await this.program.methods
-      .joinBattleground(attack, defense, collectionWhitelistProof, holderWhitelistProof)
+      .joinBattleground(
+        attack,
+        defense,
+        collectionWhitelistProof,
+        holderWhitelistProof,
+        statsProof,
+        team
+      )
       .accounts({
         signer: this.program.provider.publicKey,
         devFund: gameMaster,
@@ -83,21 +80,47 @@ class Participant {
         nftMint: this.nft,
         nftMetadata: this.nftMetadata,
         potAccount,
+        tokenProgram: await this.battleground.getPotTokenProgram(),
         devAccount,
         creatorAccount,
         playerAccount,
         playerNftTokenAccount,
+        nftEdition: this.nftEdition,
+        nftVault: await this.nftTokenAccount(this.addresses.authority),
+        nftTokenProgram: this.nftTokenProgram,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async revive() {
+    const potAccount = await this.battleground.potAccount(this.addresses.authority);
+    const playerAccount = await this.battleground.potAccount(this.program.provider.publicKey);
+    const playerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
+
+    const tx = await this.program.methods
+      .revive()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        authority: this.addresses.authority,
+        battleground: this.addresses.battleground,
+        participant: this.addresses.participant,
+        potMint: this.addresses.potMint,
+        potAccount,
+        playerAccount,
+        playerNftTokenAccount,
+        tokenProgram: await this.battleground.getPotTokenProgram(),
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
       })
       .rpc();
     await this.program.provider.connection.confirmTransaction(tx);
   }
 
+  // A taunted participant passes its scout, to harm another target once the scout is out
   async action(target: Participant, actionType: ActionType, actionPoints: number) {
-    const playerNftTokenAccount = await getAssociatedTokenAddress(
-      this.nft,
-      this.program.provider.publicKey,
-      true
-    );
+    const playerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
+    const { tauntedBy } = await this.getParticipantState();
 
     const tx = await this.program.methods
       .participantAction(actionType, actionPoints)
@@ -110,26 +133,17 @@ class Participant {
         playerNftTokenAccount,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
       })
+      .remainingAccounts(
+        tauntedBy ? [{ pubkey: tauntedBy, isSigner: false, isWritable: false }] : []
+      )
       .rpc();
     await this.program.provider.connection.confirmTransaction(tx);
   }
 
   async finishBattle() {
-    const potAccount = await getAssociatedTokenAddress(
-      this.addresses.potMint,
-      this.addresses.authority,
-      true
-    );
-    const winnerAccount = await getAssociatedTokenAddress(
-      this.addresses.potMint,
-      this.program.provider.publicKey,
-      true
-    );
-    const winnerNftTokenAccount = await getAssociatedTokenAddress(
-      this.nft,
-      this.program.provider.publicKey,
-      true
-    );
+    const potAccount = await this.battleground.potAccount(this.addresses.authority);
+    const winnerAccount = await this.battleground.potAccount(this.program.provider.publicKey);
+    const winnerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
 
     const tx = await this.program.methods
       .finishBattle()
@@ -142,34 +156,108 @@ class Participant {
         nftMint: this.nft,
         potMint: this.addresses.potMint,
         potAccount,
+        tokenProgram: await this.battleground.getPotTokenProgram(),
         winnerAccount,
         winnerNftTokenAccount,
+        nftEdition: this.nftEdition,
+        nftVault: await this.nftTokenAccount(this.addresses.authority),
+        nftTokenProgram: this.nftTokenProgram,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
       })
       .rpc({ skipPreflight: true });
     await this.program.provider.connection.confirmTransaction(tx);
   }
 
+  async claimPrize() {
+    const potAccount = await this.battleground.potAccount(this.addresses.authority);
+    const playerAccount = await this.battleground.potAccount(this.program.provider.publicKey);
+    const playerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
+
+    const tx = await this.program.methods
+      .claimPrize()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        authority: this.addresses.authority,
+        battleground: this.addresses.battleground,
+        participant: this.addresses.participant,
+        potMint: this.addresses.potMint,
+        nftMint: this.nft,
+        potAccount,
+        tokenProgram: await this.battleground.getPotTokenProgram(),
+        playerAccount,
+        playerNftTokenAccount,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
+  async claimRefund() {
+    const potAccount = await this.battleground.potAccount(this.addresses.authority);
+    const playerAccount = await this.battleground.potAccount(this.program.provider.publicKey);
+    const playerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
+
+    const tx = await this.program.methods
+      .claimRefund()
+      .accounts({
+        signer: this.program.provider.publicKey,
+        battleRoyale: this.addresses.battleRoyale,
+        authority: this.addresses.authority,
+        battleground: this.addresses.battleground,
+        participant: this.addresses.participant,
+        potMint: this.addresses.potMint,
+        nftMint: this.nft,
+        potAccount,
+        tokenProgram: await this.battleground.getPotTokenProgram(),
+        playerAccount,
+        playerNftTokenAccount,
+        nftEdition: this.nftEdition,
+        nftVault: await this.nftTokenAccount(this.addresses.authority),
+        nftTokenProgram: this.nftTokenProgram,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+      })
+      .rpc();
+    await this.program.provider.connection.confirmTransaction(tx);
+  }
+
   async leave() {
-    const playerNftTokenAccount = await getAssociatedTokenAddress(
-      this.nft,
-      this.program.provider.publicKey,
-      true
-    );
+    const potAccount = await this.battleground.potAccount(this.addresses.authority);
+    const playerAccount = await this.battleground.potAccount(this.program.provider.publicKey);
+    const playerNftTokenAccount = await this.nftTokenAccount(this.program.provider.publicKey);
 
     const tx = await this.program.methods
       .leaveBattleground()
       .accounts({
         signer: this.program.provider.publicKey,
         battleRoyale: this.addresses.battleRoyale,
+        authority: this.addresses.authority,
         battleground: this.addresses.battleground,
         participant: this.addresses.participant,
+        potMint: this.addresses.potMint,
         nftMint: this.nft,
+        potAccount,
+        tokenProgram: await this.battleground.getPotTokenProgram(),
+        playerAccount,
         playerNftTokenAccount,
+        nftEdition: this.nftEdition,
+        nftVault: await this.nftTokenAccount(this.addresses.authority),
+        nftTokenProgram: this.nftTokenProgram,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
       })
       .rpc({ skipPreflight: true });
     await this.program.provider.connection.confirmTransaction(tx);
   }
 
+  // The NFT can belong to either the original token program or Token-2022
+  async nftTokenAccount(owner: anchor.web3.PublicKey) {
+    if (!this.nftTokenProgram) {
+      this.nftTokenProgram = (
+        await this.program.provider.connection.getAccountInfo(this.nft)
+      ).owner;
+    }
+    return await getAssociatedTokenAddress(this.nft, owner, true, this.nftTokenProgram);
+  }
+
   async getParticipantState() {
     return await this.program.account.participantState.fetch(this.addresses.participant);
   }
@@ -13,10 +13,114 @@ export interface CollectionInfo {
   };
 }
 
+export interface CombatConfig {
+  baseHealthPoints: number;
+  healthPointsPerDefense: number;
+  baseAttack: number;
+  baseDefense: number;
+  healDivisor: number;
+  maxStatistics: number;
+  defenseMitigation: number;
+  minDamage: number;
+  critChance: number;
+  dodgeChance: number;
+  critMultiplier: number;
+}
+
+export interface ReviveConfig {
+  fee: anchor.BN;
+  maxRevives: number;
+  healthPoints: number;
+  minParticipants: number;
+}
+
+export interface StormConfig {
+  gracePeriod: anchor.BN;
+  damagePerDay: number;
+  idlePeriod: anchor.BN;
+}
+
+export interface StatsSource {
+  declared?: {};
+  statTable?: {
+    root: number[];
+  };
+  mintHash?: {};
+}
+
+export interface TeamMode {
+  freeForAll?: {};
+  byCollection?: {
+    collections: CollectionInfo[];
+  };
+  playerChoice?: {
+    teams: number;
+  };
+}
+
+export interface TeamConfig {
+  teamMode: TeamMode;
+  friendlyFire?: boolean;
+  healPolicy?: HealPolicy;
+}
+
+export interface ActionConfig {
+  maxActionPointsBank?: number;
+  cooldown?: anchor.BN;
+}
+
+export interface CustodyConfig {
+  nftCustody: NftCustody;
+  forfeitRecipient?: ForfeitRecipient;
+}
+
+// Optional settings of a battleground, left out ones take their default
+export interface BattlegroundOptions {
+  whitelistRoot?: number[];
+  maxDuration?: anchor.BN;
+  payoutTable?: number[];
+  minParticipants?: number;
+  scheduledStart?: anchor.BN;
+  feesRefundable?: boolean;
+  championFreeEntry?: boolean;
+  potKind?: PotKind;
+  statsSource?: StatsSource;
+  combatConfig?: CombatConfig;
+  teamConfig?: TeamConfig;
+  reviveConfig?: ReviveConfig;
+  stormConfig?: StormConfig;
+  actionConfig?: ActionConfig;
+  custodyConfig?: CustodyConfig;
+}
+
 export enum BattlegroundStatus {
   Preparing = "preparing",
   Ongoing = "ongoing",
   Finished = "finished",
+  Cancelled = "cancelled",
+}
+
+export enum PotKind {
+  Token = "token",
+  Native = "native",
+}
+
+export enum HealPolicy {
+  Any = "any",
+  SelfOnly = "selfOnly",
+  TeamOnly = "teamOnly",
+}
+
+export enum NftCustody {
+  Wallet = "wallet",
+  SoftStake = "softStake",
+  Escrow = "escrow",
+}
+
+export enum ForfeitRecipient {
+  Winner = "winner",
+  Creator = "creator",
+  Burn = "burn",
 }
 
 export declare type BattleRoyaleAccount =